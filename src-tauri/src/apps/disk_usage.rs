@@ -0,0 +1,114 @@
+//! 单个文件的磁盘占用：逻辑大小、实际分配大小，以及用于硬链接去重的文件标识。
+
+use std::fs::Metadata;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FileUsage {
+    /// 文件内容长度 (`metadata.len()`)。
+    pub(crate) apparent: u64,
+    /// 实际占用的磁盘空间；NTFS 压缩、稀疏文件与云端占位文件会明显小于逻辑大小。
+    pub(crate) allocated: u64,
+    /// (设备/卷序列号, inode/文件 id)，仅在文件存在多个硬链接时返回。
+    pub(crate) link_id: Option<(u64, u64)>,
+}
+
+#[cfg(unix)]
+pub(crate) fn file_usage(_path: &Path, meta: &Metadata) -> FileUsage {
+    use std::os::unix::fs::MetadataExt;
+
+    FileUsage {
+        apparent: meta.len(),
+        allocated: meta.blocks().saturating_mul(512),
+        link_id: (meta.nlink() > 1).then(|| (meta.dev(), meta.ino())),
+    }
+}
+
+/// 每个普通文件都打开句柄查询：目录列表中没有硬链接数，小文件同样可能有多个硬链接
+/// （WinSxS、pnpm 存储），不查询就无法去重。
+#[cfg(windows)]
+pub(crate) fn file_usage(path: &Path, meta: &Metadata) -> FileUsage {
+    let apparent = meta.len();
+    windows_file_usage(path, apparent).unwrap_or(FileUsage {
+        apparent,
+        allocated: apparent,
+        link_id: None,
+    })
+}
+
+/// 只以 FILE_READ_ATTRIBUTES 打开句柄，不会触发云端占位文件的下载。
+#[cfg(windows)]
+fn windows_file_usage(path: &Path, apparent: u64) -> Option<FileUsage> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileStandardInfo, GetFileInformationByHandle, GetFileInformationByHandleEx,
+        BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+        FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+        FILE_STANDARD_INFO,
+    };
+
+    let file = std::fs::OpenOptions::new()
+        .access_mode(FILE_READ_ATTRIBUTES)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(path)
+        .ok()?;
+    let handle = file.as_raw_handle();
+
+    // SAFETY: 句柄在 `file` 存活期间有效，缓冲区大小与结构体一致。
+    let mut standard: FILE_STANDARD_INFO = unsafe { std::mem::zeroed() };
+    let ok = unsafe {
+        GetFileInformationByHandleEx(
+            handle,
+            FileStandardInfo,
+            &mut standard as *mut _ as *mut core::ffi::c_void,
+            std::mem::size_of::<FILE_STANDARD_INFO>() as u32,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+
+    let link_id = if standard.NumberOfLinks > 1 {
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        let ok = unsafe { GetFileInformationByHandle(handle, &mut info) };
+        (ok != 0).then(|| {
+            (
+                info.dwVolumeSerialNumber as u64,
+                ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+            )
+        })
+    } else {
+        None
+    };
+
+    Some(FileUsage {
+        apparent,
+        allocated: standard.AllocationSize.max(0) as u64,
+        link_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_links_share_one_link_id() {
+        let dir = std::env::temp_dir().join(format!("appmanager-usage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, plain) = (dir.join("a.dll"), dir.join("b.dll"), dir.join("c.dll"));
+        std::fs::write(&a, [0u8; 100]).unwrap();
+        std::fs::hard_link(&a, &b).unwrap();
+        std::fs::write(&plain, [0u8; 100]).unwrap();
+        let usage = |p: &Path| file_usage(p, &std::fs::metadata(p).unwrap());
+        let (ua, ub, uc) = (usage(&a), usage(&b), usage(&plain));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(ua.link_id.is_some());
+        assert_eq!(ua.link_id, ub.link_id);
+        assert_eq!(uc.link_id, None);
+        assert_eq!(ua.apparent, 100);
+    }
+}
@@ -1,10 +1,56 @@
+/// 条目内按文件用途划分的逻辑大小，例如缓存、日志、数据库。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryBytes {
+    pub kind: String,
+    pub label: String,
+    pub bytes: u64,
+}
+
 #[derive(serde::Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AppBreakdownEntry {
     pub kind: String,
     pub label: String,
     pub bytes: u64,
+    /// 实际占用的磁盘空间（考虑压缩、稀疏与云端占位文件）。
+    pub allocated_bytes: u64,
     pub paths: Vec<String>,
+    /// `shared` 条目的全部合理归属软件名；其他条目为空。
+    pub shared_owners: Vec<String>,
+    /// 按文件用途的细分，按大小降序；无法遍历的条目（如注册表估算）为空。
+    pub categories: Vec<CategoryBytes>,
+    /// 条目内文件最近一次修改或访问的时间（Unix 秒）；未能读取时为空。
+    pub last_activity: Option<u64>,
+    /// 抽样统计时约 95% 置信度的误差范围（± 字节）；精确统计时为空。
+    pub error_bytes: Option<u64>,
+    /// 因权限不足、统计期间被删除或路径过长而未能计入的文件或目录数。
+    pub skipped_count: u32,
+    /// 条目目录下通过链接指向别处（常见为迁移到其他盘）的文件夹。
+    pub relocated: Vec<RelocatedFolder>,
+    /// 快速估算得到的临时值，稍后会被精确统计的结果替换。
+    pub estimated: bool,
+}
+
+/// 通过符号链接、目录联接或卷挂载点指向别处的文件夹。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocatedFolder {
+    pub path: String,
+    /// 链接最终指向的目录。
+    pub target: String,
+    /// 目标所在的卷，如 `D:`；未分配盘符的挂载点为卷 GUID 路径。
+    pub volume: String,
+    /// 按 `LinkPolicy::Follow` 跟随时统计到的目标大小，不计入所在条目；未跟随时为空。
+    pub bytes: Option<u64>,
+}
+
+/// 跟随链接统计到的、位于某个卷上的大小。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeBytes {
+    pub volume: String,
+    pub bytes: u64,
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -13,8 +59,72 @@ pub struct AppRecord {
     pub id: String,
     pub name: String,
     pub publisher: Option<String>,
+    /// 归一化后的厂商 id，同一厂商的不同写法（如 "Valve" 与 "Valve Corporation"）一致。
+    pub vendor_id: Option<String>,
+    pub vendor_name: Option<String>,
     pub total_bytes: u64,
+    pub total_allocated_bytes: u64,
     pub breakdown: Vec<AppBreakdownEntry>,
+    /// 各条目中最新的 `last_activity`，即该软件最后一次被使用或改动的大致时间。
+    pub last_activity: Option<u64>,
+    /// 各抽样条目误差合并后的范围；没有抽样条目时为空。
+    pub error_bytes: Option<u64>,
+    /// 有条目存在未能读取的路径，`total_bytes` 可能偏小。
+    pub incomplete: bool,
+    /// 按 `LinkPolicy::Follow` 跟随链接统计到的目标大小，按所在卷汇总（同一目标只计一次），
+    /// 不计入 `total_bytes`；未跟随时为空。
+    pub followed_volumes: Vec<VolumeBytes>,
+    /// 快速估算得到的临时记录；同一 id 的精确记录随后发送。
+    pub estimated: bool,
+}
+
+/// 多个软件共同拥有同一文件夹时的大小分摊方式。
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SharedSplit {
+    #[default]
+    Even,
+    ByScore,
+    /// 按 app id 指定权重，未列出的软件权重为 1；负数按 0 计，全为 0 时平均分摊。
+    Weights(std::collections::HashMap<String, f64>),
+}
+
+/// 遍历遇到符号链接、目录联接与卷挂载点时的处理方式。
+#[derive(serde::Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkPolicy {
+    /// 不跟随，链接及其目标都不计入。
+    #[default]
+    Skip,
+    /// 只计入链接自身的占用。
+    LinkOnly,
+    /// 链接自身计入所在文件夹；另外统计目标的大小，按目标所在的卷单独报告，
+    /// 不计入 `total_bytes`。
+    Follow,
+}
+
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanOptions {
+    pub shared_split: SharedSplit,
+    /// 按注册表估算大小从大到小统计，让占用最多的软件先出现；默认按名称顺序。
+    pub largest_first: bool,
+    /// 以抽样统计代替精确统计的条目类型（与 `AppBreakdownEntry.kind` 相同），
+    /// 适合游戏库、虚拟机等数 TB 的目录；结果带有误差范围。
+    pub approximate: Vec<String>,
+    pub link_policy: LinkPolicy,
+    /// 设置后以后台模式扫描：单线程、降低优先级并按限速进行，适合定时任务。
+    pub background: Option<BackgroundOptions>,
+}
+
+/// 后台扫描的限速；两项都为 0 时只降低优先级、不限速。
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackgroundOptions {
+    /// 每秒最多读取多少个文件或目录项的信息。
+    pub max_files_per_sec: u32,
+    /// 每秒最多统计多少字节的文件；大文件集中的目录会相应放慢。
+    pub max_bytes_per_sec: u64,
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -24,6 +134,49 @@ pub struct ScanProgress {
     pub current: u32,
     pub total: u32,
     pub message: String,
+    /// 精确统计期间定期附带的目录遍历进度；其他阶段为空。
+    pub walk: Option<WalkProgress>,
+    /// 已完成阶段的耗时，按完成顺序排列。
+    pub phase_timings: Vec<PhaseTiming>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkProgress {
+    /// 已读取的目录项数（文件与子目录），包括从大小索引复用的目录。
+    pub files_seen: u64,
+    pub bytes_seen: u64,
+    /// 最近处理的目录。
+    pub current_path: String,
+    pub files_per_sec: f64,
+    /// 按上次扫描保存的目录项总数推算的剩余秒数；没有索引或已超出上次总数时为空。
+    pub eta_secs: Option<u64>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub millis: u64,
+}
+
+/// 审计选项，前端可只传需要修改的字段。
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditOptions {
+    /// 超过多少个月没有修改或访问记录的软件视为闲置。
+    pub stale_months: u32,
+    /// 只列出总占用不小于该值的闲置软件。
+    pub stale_min_bytes: u64,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        AuditOptions {
+            stale_months: 6,
+            stale_min_bytes: 1024 * 1024 * 1024,
+        }
+    }
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -32,6 +185,8 @@ pub struct AuditRootSummary {
     pub kind: String,
     pub assigned_folders: u32,
     pub unassigned_folders: u32,
+    /// 该根目录下统计时未能读取的路径数。
+    pub skipped_paths: u32,
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -47,6 +202,15 @@ pub struct AuditUnassignedFolder {
     pub kind: String,
     pub folder: String,
     pub path: String,
+    pub bytes: u64,
+}
+
+/// 审计面板中手动归属文件夹时可选的软件。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditApp {
+    pub id: String,
+    pub name: String,
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -57,6 +221,265 @@ pub struct AuditOverview {
     pub roots: Vec<AuditRootSummary>,
     pub duplicate_install_locations: Vec<AuditDuplicateInstallLocation>,
     pub unassigned_folders: Vec<AuditUnassignedFolder>,
+    /// 占用大且长期未使用的软件，按占用降序，最值得优先卸载。
+    pub stale_apps: Vec<AuditStaleApp>,
+    /// 统计大小时未能读取的路径，相关软件的大小因此偏小。
+    pub skipped_paths: Vec<AuditSkippedPath>,
+    pub skipped_path_count: u32,
+    /// 通过链接迁移到别处的文件夹及其实际所在的卷。
+    pub relocated_folders: Vec<AuditRelocatedFolder>,
+    /// 已从卸载列表中消失、但 AppData/ProgramData 中仍留有数据的软件，按残留大小降序。
+    pub orphaned_apps: Vec<AuditOrphanedApp>,
+    /// 指向不存在的路径、或登记大小与实际明显不符的卸载条目。
+    pub broken_uninstall_entries: Vec<AuditBrokenUninstallEntry>,
+    pub apps: Vec<AuditApp>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRelocatedFolder {
+    /// 所在根目录类型，与 `AuditSkippedPath.kind` 相同。
+    pub kind: String,
+    pub path: String,
+    pub target: String,
+    pub volume: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditSkippedPath {
+    /// 所在根目录类型，与 `AuditRootSummary.kind` 相同；安装目录为 `program`。
+    pub kind: String,
+    pub path: String,
+    /// `permissionDenied`、`vanished`、`pathTooLong` 或 `other`。
+    pub reason: String,
+    pub label: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditStaleApp {
+    pub id: String,
+    pub name: String,
+    pub total_bytes: u64,
+    /// 最近一次修改或访问的时间，Unix 秒。
+    pub last_activity: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditOrphanedApp {
+    pub id: String,
+    pub name: String,
+    pub publisher: Option<String>,
+    /// 最后一次在卸载列表中看到它的扫描时间，Unix 秒。
+    pub last_seen: u64,
+    /// 首次发现它已被卸载的扫描时间，Unix 秒；实际卸载发生在 `last_seen` 与此之间。
+    pub uninstalled_at: u64,
+    pub total_bytes: u64,
+    pub folders: Vec<AuditOrphanedFolder>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditOrphanedFolder {
+    /// 所在根目录类型，与 `AuditSkippedPath.kind` 相同。
+    pub kind: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditBrokenUninstallEntry {
+    pub id: String,
+    pub name: String,
+    /// 完整的注册表项路径，如 `HKEY_LOCAL_MACHINE\SOFTWARE\...\Uninstall\{GUID}`；
+    /// 同一软件在多个配置单元中登记时全部列出。
+    pub registry_keys: Vec<String>,
+    pub issues: Vec<AuditUninstallIssue>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditUninstallIssue {
+    /// `installLocationMissing`、`uninstallerMissing`、`iconMissing` 或 `sizeMismatch`。
+    pub reason: String,
+    pub label: String,
+    /// 出问题的路径；大小不符时为安装目录。
+    pub path: String,
+    /// 仅 `sizeMismatch`：注册表登记的大小与实际统计的大小。
+    pub estimated_bytes: Option<u64>,
+    pub measured_bytes: Option<u64>,
+}
+
+/// 明细条目下逐层浏览时的一个子项。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DrillDownEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub bytes: u64,
+    pub allocated_bytes: u64,
+}
+
+/// 一层浏览结果：按大小降序的前 N 项，其余合并计数。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DrillDownLevel {
+    /// 当前目录；`None` 表示该条目的顶层目录列表。
+    pub path: Option<String>,
+    /// 上一层目录；`None` 时返回顶层目录列表。
+    pub parent: Option<String>,
+    pub entries: Vec<DrillDownEntry>,
+    pub remaining_count: u32,
+    pub remaining_bytes: u64,
+}
+
+/// 重复文件查找选项，前端可只传需要修改的字段。
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DuplicateOptions {
+    /// 小于该值的文件不参与比较：数量多、能省下的空间少。
+    pub min_file_bytes: u64,
+    /// 最多返回的重复组数，按浪费的空间降序。
+    pub limit: u32,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        DuplicateOptions {
+            min_file_bytes: 1024 * 1024,
+            limit: 200,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateFile {
+    pub path: String,
+    /// 归属的软件名；共享目录中的文件可能有多个。
+    pub apps: Vec<String>,
+}
+
+/// 内容完全相同的一组文件。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSet {
+    pub file_bytes: u64,
+    /// 只保留一份时可以省下的空间。
+    pub wasted_bytes: u64,
+    pub files: Vec<DuplicateFile>,
+    /// 涉及的全部软件名。
+    pub apps: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    pub sets: Vec<DuplicateSet>,
+    /// 截断前的重复组总数与浪费空间总和。
+    pub set_count: u32,
+    pub wasted_bytes: u64,
+    pub files_scanned: u32,
+    /// 从上次（包括被取消的）查找保存的哈希中直接复用的文件数。
+    pub reused_hashes: u32,
+}
+
+/// 跨会话目录大小索引的概况。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeIndexInfo {
+    pub path: Option<String>,
+    pub directory_count: u32,
+    pub index_bytes: u64,
+    /// 最近一次写入时间（Unix 秒）。
+    pub updated_at: Option<u64>,
+}
+
+/// 文件夹名与软件 token 匹配的得分阈值。默认值即扫描使用的阈值，
+/// 离线评估时可调整，比较不同阈值下的准确率与召回率。
+#[derive(Clone)]
+pub struct MatchParams {
+    /// 名称 token 命中时的最低得分（name * 100 + publisher）。
+    pub name_threshold: i32,
+    /// 仅凭厂商 token 匹配时的最低得分（publisher * 50）。
+    pub publisher_threshold: i32,
+}
+
+/// 归因准确率评估用的标注数据集。
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchEvalDataset {
+    pub apps: Vec<MatchEvalApp>,
+    pub folders: Vec<MatchEvalFolder>,
+    /// 参与评估的用户修正，与审计面板中记录的修正作用相同。
+    #[serde(default)]
+    pub corrections: Vec<MatchEvalCorrection>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchEvalApp {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub publisher: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchEvalFolder {
+    /// `appDataLocal` / `appDataRoaming` / `appDataLocalLow` / `programData`
+    pub root: String,
+    pub folder: String,
+    /// 期望归属的 app id；`None` 表示不应归属任何软件。
+    #[serde(default)]
+    pub expected: Option<String>,
+    /// 文件夹中可执行文件的版本资源，对应扫描时读取到的证据。
+    #[serde(default)]
+    pub evidence: Vec<MatchEvalEvidence>,
+}
+
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatchEvalEvidence {
+    pub company_name: Option<String>,
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+    pub product_version: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchEvalCorrection {
+    pub root: String,
+    pub folder: String,
+    /// 修正指定的 app id。
+    pub app: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchMisattribution {
+    pub root: String,
+    pub folder: String,
+    pub expected: Option<String>,
+    pub predicted: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchEvalReport {
+    pub folder_count: u32,
+    pub expected_count: u32,
+    pub predicted_count: u32,
+    pub correct_count: u32,
+    pub precision: f64,
+    pub recall: f64,
+    pub misattributions: Vec<MatchMisattribution>,
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -69,7 +492,14 @@ pub struct DiskInfo {
     pub is_removable: bool,
 }
 
-#[cfg(windows)]
+#[cfg_attr(not(windows), allow(dead_code))]
+mod disk_usage;
+#[cfg_attr(not(windows), allow(dead_code))]
+mod pe;
+#[cfg_attr(not(windows), allow(dead_code))]
+mod throttle;
+// 归因逻辑是纯数据处理，离线评估在任何平台上都能运行；其余入口只在 Windows 上调用。
+#[cfg_attr(not(windows), allow(dead_code))]
 mod windows;
 
 pub fn get_disk_info() -> Vec<DiskInfo> {
@@ -85,56 +515,268 @@ pub fn get_disk_info() -> Vec<DiskInfo> {
 pub fn scan_apps() -> Vec<AppRecord> {
     #[cfg(windows)]
     {
-        return windows::scan_apps_windows();
+        return windows::scan_apps_windows(&ScanOptions::default());
     }
 
     #[cfg(not(windows))]
     Vec::new()
 }
 
-pub fn scan_apps_stream<FProgress, FRecord>(on_progress: FProgress, on_record: FRecord)
+/// 扫描与文件监视的取消与暂停标志，可在线程间共享。
+#[derive(Clone, Default)]
+pub struct CancelToken(std::sync::Arc<ControlFlags>);
+
+#[derive(Default)]
+struct ControlFlags {
+    cancelled: std::sync::atomic::AtomicBool,
+    paused: std::sync::atomic::AtomicBool,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0
+            .cancelled
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// 暂停后目录遍历在处理下一个目录前等待，直到恢复或取消。
+    pub fn pause(&self) {
+        self.0
+            .paused
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.0
+            .paused
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// 一次扫描的内部状态（归因结果与大小缓存），交给 [`watch_apps`] 做增量更新。
+pub struct ScanSnapshot {
+    #[cfg(windows)]
+    inner: windows::ScanSnapshot,
+}
+
+/// 流式扫描；`cancel` 被触发后尽快停止目录遍历并返回 `None`。
+pub fn scan_apps_stream<FProgress, FRecord>(
+    options: &ScanOptions,
+    cancel: &CancelToken,
+    on_progress: FProgress,
+    on_record: FRecord,
+) -> Option<ScanSnapshot>
 where
     FProgress: FnMut(ScanProgress),
     FRecord: FnMut(AppRecord),
 {
     #[cfg(windows)]
     {
-        windows::scan_apps_stream_windows(on_progress, on_record);
-        return;
+        return windows::scan_apps_stream_windows(options, cancel, on_progress, on_record)
+            .map(|inner| ScanSnapshot { inner });
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = (options, cancel, on_progress, on_record);
+        Some(ScanSnapshot {})
+    }
+}
+
+/// 监视已归因的目录，变化后只重新统计受影响的子树，并把更新后的记录交给 `on_update`。
+/// 阻塞直到 `stop` 被取消。
+pub fn watch_apps<F>(
+    snapshot: &ScanSnapshot,
+    options: &ScanOptions,
+    stop: &CancelToken,
+    on_update: F,
+) where
+    F: FnMut(AppRecord),
+{
+    #[cfg(windows)]
+    {
+        windows::watch_apps_windows(&snapshot.inner, options, stop, on_update);
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = (snapshot, options, stop, on_update);
+    }
+}
+
+/// 逐层浏览扫描结果中某个软件的明细条目（`kind` 与 `AppBreakdownEntry.kind` 相同），
+/// 返回最大的 `limit` 个子目录与文件。
+pub fn drill_down(
+    snapshot: &ScanSnapshot,
+    app_id: &str,
+    kind: &str,
+    path: Option<&str>,
+    limit: Option<u32>,
+) -> Result<DrillDownLevel, String> {
+    #[cfg(windows)]
+    {
+        return windows::drill_down_windows(&snapshot.inner, app_id, kind, path, limit);
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = (snapshot, app_id, kind, path, limit);
+        Err("仅支持 Windows".into())
+    }
+}
+
+/// 在扫描归因到的目录中查找内容相同的文件：依次按大小、文件开头的哈希与完整哈希分组。
+/// `cancel` 被触发后返回 `None`；已算出的哈希会保存下来，下次查找从中断处继续。
+pub fn find_duplicates<F>(
+    snapshot: &ScanSnapshot,
+    options: &DuplicateOptions,
+    cancel: &CancelToken,
+    on_progress: F,
+) -> Option<DuplicateReport>
+where
+    F: FnMut(ScanProgress),
+{
+    #[cfg(windows)]
+    {
+        return windows::find_duplicates_windows(&snapshot.inner, options, cancel, on_progress);
     }
 
     #[cfg(not(windows))]
     {
-        let _ = (on_progress, on_record);
+        let _ = (snapshot, options, cancel, on_progress);
+        Some(DuplicateReport::default())
     }
 }
 
-pub fn audit_overview() -> AuditOverview {
+/// 基于最近一次扫描的软件列表、归属结果与大小缓存生成审计报告，与扫描结果一致，
+/// 不再重新读取注册表或遍历目录。
+pub fn audit_overview(snapshot: &ScanSnapshot, options: &AuditOptions) -> AuditOverview {
     #[cfg(windows)]
     {
-        return windows::audit_overview_windows();
+        return windows::audit_overview_windows(&snapshot.inner, options);
     }
 
     #[cfg(not(windows))]
-    AuditOverview {
-        app_count: 0,
-        unknown_program_size_count: 0,
-        roots: Vec::new(),
-        duplicate_install_locations: Vec::new(),
-        unassigned_folders: Vec::new(),
+    {
+        let _ = (snapshot, options);
+        AuditOverview {
+            app_count: 0,
+            unknown_program_size_count: 0,
+            roots: Vec::new(),
+            duplicate_install_locations: Vec::new(),
+            unassigned_folders: Vec::new(),
+            stale_apps: Vec::new(),
+            skipped_paths: Vec::new(),
+            skipped_path_count: 0,
+            relocated_folders: Vec::new(),
+            orphaned_apps: Vec::new(),
+            broken_uninstall_entries: Vec::new(),
+            apps: Vec::new(),
+        }
     }
 }
 
-pub fn measure_folder_size(kind: &str, folder: &str) -> u64 {
+/// 统计某个根目录下顶层文件夹的大小，复用最近一次扫描的大小缓存。
+pub fn measure_audit_folder_size(
+    snapshot: &ScanSnapshot,
+    kind: &str,
+    folder: &str,
+) -> Result<u64, String> {
     #[cfg(windows)]
     {
-        return windows::measure_folder_size_windows(kind, folder);
+        return windows::measure_audit_folder_size_windows(&snapshot.inner, kind, folder);
     }
 
     #[cfg(not(windows))]
     {
+        let _ = (snapshot, kind, folder);
+        Err("仅支持 Windows".into())
+    }
+}
+
+/// 记录用户把某个文件夹归属到指定软件的修正，后续扫描会优先采用；
+/// 同时更新快照中的归属，返回该软件更新后的记录，审计与明细立即反映这一归属。
+pub fn assign_folder(
+    snapshot: &ScanSnapshot,
+    kind: &str,
+    folder: &str,
+    app_id: &str,
+) -> Result<AppRecord, String> {
+    #[cfg(windows)]
+    {
+        return windows::assign_folder_windows(&snapshot.inner, kind, folder, app_id);
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = snapshot;
         let _ = kind;
         let _ = folder;
-        0
+        let _ = app_id;
+        Err("仅支持 Windows".into())
+    }
+}
+
+/// 导出修正集（JSON），便于在多台机器间共享。
+pub fn export_corrections() -> String {
+    #[cfg(windows)]
+    {
+        return windows::export_corrections_windows();
+    }
+
+    #[cfg(not(windows))]
+    String::new()
+}
+
+/// 合并导入的修正集，返回新增条数。
+pub fn import_corrections(json: &str) -> Result<u32, String> {
+    #[cfg(windows)]
+    {
+        return windows::import_corrections_windows(json);
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = json;
+        Err("仅支持 Windows".into())
     }
 }
+
+pub fn size_index_info() -> SizeIndexInfo {
+    #[cfg(windows)]
+    {
+        return windows::size_index_info_windows();
+    }
+
+    #[cfg(not(windows))]
+    SizeIndexInfo {
+        path: None,
+        directory_count: 0,
+        index_bytes: 0,
+        updated_at: None,
+    }
+}
+
+/// 删除目录大小索引，下次扫描将完整重新统计。
+pub fn clear_size_index() -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        return windows::clear_size_index_windows();
+    }
+
+    #[cfg(not(windows))]
+    Ok(())
+}
+
+/// 用标注数据集离线评估归因准确率；不访问注册表与文件系统，任何平台上都能运行。
+pub fn evaluate_matching(dataset: &MatchEvalDataset, params: &MatchParams) -> MatchEvalReport {
+    windows::evaluate_matching_offline(dataset, params)
+}
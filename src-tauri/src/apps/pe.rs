@@ -0,0 +1,470 @@
+//! 纯 Rust 实现的 PE 版本资源 (VS_VERSIONINFO) 解析，不依赖 Windows API，
+//! 因此可以在任意平台上对样本文件进行解析。
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const RT_VERSION: u32 = 16;
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+const MAX_HEADER_BYTES: usize = 4096;
+const MAX_RESOURCE_BYTES: u32 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct VersionInfo {
+    pub(crate) company_name: Option<String>,
+    pub(crate) product_name: Option<String>,
+    pub(crate) file_description: Option<String>,
+    pub(crate) product_version: Option<String>,
+}
+
+struct ResourceSection {
+    virtual_address: u32,
+    raw_offset: u32,
+    raw_size: u32,
+    dir_rva: u32,
+}
+
+/// 解析完整 PE 镜像中的版本资源；结构损坏或没有任何可用字段时返回 `None`。
+pub(crate) fn parse_version_info(image: &[u8]) -> Option<VersionInfo> {
+    let section = locate_resource_section(image)?;
+    let start = section.raw_offset as usize;
+    let end = start
+        .checked_add(section.raw_size as usize)?
+        .min(image.len());
+    parse_resource_section(image.get(start..end)?, &section)
+}
+
+/// 只读取文件头和 `.rsrc` 节，避免把大型可执行文件整体读入内存。
+pub(crate) fn read_version_info(path: &Path) -> Option<VersionInfo> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut header = Vec::with_capacity(MAX_HEADER_BYTES);
+    file.by_ref()
+        .take(MAX_HEADER_BYTES as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    let section = locate_resource_section(&header)?;
+    if section.raw_size == 0 || section.raw_size > MAX_RESOURCE_BYTES {
+        return None;
+    }
+
+    let mut rsrc = vec![0u8; section.raw_size as usize];
+    file.seek(SeekFrom::Start(section.raw_offset as u64)).ok()?;
+    let read = read_up_to(&mut file, &mut rsrc).ok()?;
+    rsrc.truncate(read);
+    parse_resource_section(&rsrc, &section)
+}
+
+fn read_up_to(file: &mut std::fs::File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+fn locate_resource_section(header: &[u8]) -> Option<ResourceSection> {
+    if header.get(0..2)? != b"MZ" {
+        return None;
+    }
+    let pe = read_u32(header, 0x3C)? as usize;
+    if header.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    let coff = pe + 4;
+    let section_count = read_u16(header, coff + 2)? as usize;
+    let optional_size = read_u16(header, coff + 16)? as usize;
+    let optional = coff + 20;
+
+    let (count_offset, dirs_offset) = match read_u16(header, optional)? {
+        0x10b => (92, 96),
+        0x20b => (108, 112),
+        _ => return None,
+    };
+    let dir_count = read_u32(header, optional + count_offset)? as usize;
+    if dir_count <= IMAGE_DIRECTORY_ENTRY_RESOURCE {
+        return None;
+    }
+    let dir = optional + dirs_offset + IMAGE_DIRECTORY_ENTRY_RESOURCE * 8;
+    let dir_rva = read_u32(header, dir)?;
+    if dir_rva == 0 {
+        return None;
+    }
+
+    let sections = optional + optional_size;
+    for i in 0..section_count {
+        let s = sections + i * 40;
+        let virtual_size = read_u32(header, s + 8)?;
+        let virtual_address = read_u32(header, s + 12)?;
+        let raw_size = read_u32(header, s + 16)?;
+        let raw_offset = read_u32(header, s + 20)?;
+        let span = virtual_size.max(raw_size);
+        if dir_rva >= virtual_address && dir_rva < virtual_address.saturating_add(span) {
+            return Some(ResourceSection {
+                virtual_address,
+                raw_offset,
+                raw_size,
+                dir_rva,
+            });
+        }
+    }
+    None
+}
+
+fn parse_resource_section(rsrc: &[u8], section: &ResourceSection) -> Option<VersionInfo> {
+    let root = (section.dir_rva - section.virtual_address) as usize;
+
+    let type_dir = find_entry(rsrc, root, Some(RT_VERSION))?;
+    let name_dir = find_entry(rsrc, root + type_dir, None)?;
+    let lang_entry = find_entry(rsrc, root + name_dir, None)?;
+
+    let data_entry = root + lang_entry;
+    let data_rva = read_u32(rsrc, data_entry)?;
+    let data_size = read_u32(rsrc, data_entry + 4)? as usize;
+    let data_start = data_rva.checked_sub(section.virtual_address)? as usize;
+    let data = rsrc.get(data_start..data_start.checked_add(data_size)?)?;
+
+    parse_vs_versioninfo(data)
+}
+
+/// 在资源目录中查找指定 ID（或第一个）条目，返回相对资源根目录的偏移。
+fn find_entry(rsrc: &[u8], dir: usize, id: Option<u32>) -> Option<usize> {
+    let named = read_u16(rsrc, dir + 12)? as usize;
+    let ids = read_u16(rsrc, dir + 14)? as usize;
+    for i in 0..named + ids {
+        let entry = dir + 16 + i * 8;
+        let name = read_u32(rsrc, entry)?;
+        let offset = read_u32(rsrc, entry + 4)?;
+        let is_match = match id {
+            Some(want) => name & 0x8000_0000 == 0 && name == want,
+            None => true,
+        };
+        if is_match {
+            return Some((offset & 0x7FFF_FFFF) as usize);
+        }
+    }
+    None
+}
+
+struct Block<'a> {
+    key: String,
+    value: &'a [u8],
+    children: &'a [u8],
+    len: usize,
+}
+
+fn parse_block(data: &[u8]) -> Option<Block<'_>> {
+    let len = read_u16(data, 0)? as usize;
+    let value_len = read_u16(data, 2)? as usize;
+    let value_type = read_u16(data, 4)?;
+    if len < 6 || len > data.len() {
+        return None;
+    }
+    let data = &data[..len];
+
+    let (key, key_end) = read_utf16z(data, 6)?;
+    let value_start = align4(key_end);
+    // 文本类型 (wType = 1) 的长度以 WCHAR 计。
+    let value_bytes = if value_type == 1 {
+        value_len * 2
+    } else {
+        value_len
+    };
+    let value_end = value_start.saturating_add(value_bytes).min(len);
+    let value = data.get(value_start.min(len)..value_end).unwrap_or(&[]);
+    let children = data.get(align4(value_end).min(len)..).unwrap_or(&[]);
+
+    Some(Block {
+        key,
+        value,
+        children,
+        len,
+    })
+}
+
+fn for_each_child<'a>(mut data: &'a [u8], mut f: impl FnMut(Block<'a>)) {
+    while let Some(block) = parse_block(data) {
+        let next = align4(block.len);
+        f(block);
+        if next >= data.len() {
+            break;
+        }
+        data = &data[next..];
+    }
+}
+
+fn parse_vs_versioninfo(data: &[u8]) -> Option<VersionInfo> {
+    let root = parse_block(data)?;
+    if root.key != "VS_VERSION_INFO" {
+        return None;
+    }
+
+    let mut info = VersionInfo::default();
+    for_each_child(root.children, |file_info| {
+        if file_info.key != "StringFileInfo" {
+            return;
+        }
+        for_each_child(file_info.children, |table| {
+            for_each_child(table.children, |s| {
+                let value = decode_utf16z(s.value);
+                let value = value.trim();
+                if value.is_empty() {
+                    return;
+                }
+                let slot = match s.key.as_str() {
+                    "CompanyName" => &mut info.company_name,
+                    "ProductName" => &mut info.product_name,
+                    "FileDescription" => &mut info.file_description,
+                    "ProductVersion" => &mut info.product_version,
+                    _ => return,
+                };
+                if slot.is_none() {
+                    *slot = Some(value.to_string());
+                }
+            });
+        });
+    });
+
+    (info != VersionInfo::default()).then_some(info)
+}
+
+fn read_utf16z(data: &[u8], start: usize) -> Option<(String, usize)> {
+    let mut units = Vec::new();
+    let mut i = start;
+    loop {
+        let u = read_u16(data, i)?;
+        i += 2;
+        if u == 0 {
+            break;
+        }
+        units.push(u);
+    }
+    Some((String::from_utf16_lossy(&units), i))
+}
+
+fn decode_utf16z(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    let b = data.get(at..at.checked_add(2)?)?;
+    Some(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    let b = data.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSRC_VA: u32 = 0x2000;
+    const RSRC_RAW: usize = 0x400;
+    /// 资源目录三层共占 88 字节，版本数据从 96 处开始。
+    const VERSION_DATA_OFFSET: u32 = 96;
+
+    fn utf16z(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .chain([0])
+            .flat_map(|u| u.to_le_bytes())
+            .collect()
+    }
+
+    fn pad4(buf: &mut Vec<u8>) {
+        buf.resize(align4(buf.len()), 0);
+    }
+
+    fn block(key: &str, value: &[u8], value_len: u16, value_type: u16, children: &[u8]) -> Vec<u8> {
+        let mut b = vec![0u8; 6];
+        b.extend(utf16z(key));
+        pad4(&mut b);
+        b.extend_from_slice(value);
+        pad4(&mut b);
+        b.extend_from_slice(children);
+        let len = b.len() as u16;
+        b[0..2].copy_from_slice(&len.to_le_bytes());
+        b[2..4].copy_from_slice(&value_len.to_le_bytes());
+        b[4..6].copy_from_slice(&value_type.to_le_bytes());
+        pad4(&mut b);
+        b
+    }
+
+    fn string(key: &str, value: &str) -> Vec<u8> {
+        let v = utf16z(value);
+        block(key, &v, (v.len() / 2) as u16, 1, &[])
+    }
+
+    fn version_info(table: &[u8]) -> Vec<u8> {
+        let table = block("040904b0", &[], 0, 1, table);
+        let file_info = block("StringFileInfo", &[], 0, 1, &table);
+        block("VS_VERSION_INFO", &[0u8; 52], 52, 0, &file_info)
+    }
+
+    fn sample_strings() -> Vec<u8> {
+        [
+            string("CompanyName", "Contoso Ltd."),
+            string("ProductName", "Contoso Viewer"),
+            string("ProductVersion", "1.2.3"),
+        ]
+        .concat()
+    }
+
+    fn put_u16(buf: &mut [u8], at: usize, v: u16) {
+        buf[at..at + 2].copy_from_slice(&v.to_le_bytes());
+    }
+
+    fn put_u32(buf: &mut [u8], at: usize, v: u32) {
+        buf[at..at + 4].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// 资源目录：RT_VERSION → 第一个名称 → 第一个语言 → 数据项。
+    fn resource_section(version: &[u8]) -> Vec<u8> {
+        let mut r = vec![0u8; VERSION_DATA_OFFSET as usize];
+        for (dir, id, target) in [
+            (0, RT_VERSION, 0x8000_0000 | 24),
+            (24, 1, 0x8000_0000 | 48),
+            (48, 0x409, 72),
+        ] {
+            put_u16(&mut r, dir + 14, 1);
+            put_u32(&mut r, dir + 16, id);
+            put_u32(&mut r, dir + 20, target);
+        }
+        put_u32(&mut r, 72, RSRC_VA + VERSION_DATA_OFFSET);
+        put_u32(&mut r, 76, version.len() as u32);
+        r.extend_from_slice(version);
+        r
+    }
+
+    fn pe_image(pe32_plus: bool, rsrc: &[u8]) -> Vec<u8> {
+        let (magic, dirs_offset) = if pe32_plus { (0x20b, 112) } else { (0x10b, 96) };
+        let pe = 0x40;
+        let optional = pe + 24;
+        let optional_size = dirs_offset + 16 * 8;
+        let section = optional + optional_size;
+
+        let mut img = vec![0u8; RSRC_RAW];
+        img[0..2].copy_from_slice(b"MZ");
+        put_u32(&mut img, 0x3C, pe as u32);
+        img[pe..pe + 4].copy_from_slice(b"PE\0\0");
+        put_u16(&mut img, pe + 6, 1);
+        put_u16(&mut img, pe + 20, optional_size as u16);
+        put_u16(&mut img, optional, magic);
+        put_u32(&mut img, optional + dirs_offset - 4, 16);
+        let dir = optional + dirs_offset + IMAGE_DIRECTORY_ENTRY_RESOURCE * 8;
+        put_u32(&mut img, dir, RSRC_VA);
+        put_u32(&mut img, dir + 4, rsrc.len() as u32);
+
+        img[section..section + 5].copy_from_slice(b".rsrc");
+        put_u32(&mut img, section + 8, rsrc.len() as u32);
+        put_u32(&mut img, section + 12, RSRC_VA);
+        put_u32(&mut img, section + 16, rsrc.len() as u32);
+        put_u32(&mut img, section + 20, RSRC_RAW as u32);
+        img.extend_from_slice(rsrc);
+        img
+    }
+
+    fn sample_image(pe32_plus: bool) -> Vec<u8> {
+        pe_image(
+            pe32_plus,
+            &resource_section(&version_info(&sample_strings())),
+        )
+    }
+
+    fn expected() -> VersionInfo {
+        VersionInfo {
+            company_name: Some("Contoso Ltd.".into()),
+            product_name: Some("Contoso Viewer".into()),
+            file_description: None,
+            product_version: Some("1.2.3".into()),
+        }
+    }
+
+    #[test]
+    fn parses_pe32_and_pe32_plus() {
+        assert_eq!(parse_version_info(&sample_image(false)), Some(expected()));
+        assert_eq!(parse_version_info(&sample_image(true)), Some(expected()));
+    }
+
+    #[test]
+    fn reads_only_header_and_rsrc_from_file() {
+        let path = std::env::temp_dir().join(format!("appmanager-pe-{}.exe", std::process::id()));
+        std::fs::write(&path, sample_image(true)).unwrap();
+        let info = read_version_info(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(info, Some(expected()));
+    }
+
+    #[test]
+    fn truncated_headers_are_rejected() {
+        let img = sample_image(false);
+        for len in [0, 1, 0x3C, 0x42, 0x60, 0x100, 0x180] {
+            assert_eq!(parse_version_info(&img[..len]), None, "len {len}");
+        }
+        assert_eq!(parse_version_info(&img[..RSRC_RAW + 40]), None);
+    }
+
+    #[test]
+    fn out_of_range_rvas_are_rejected() {
+        let rsrc = resource_section(&version_info(&sample_strings()));
+
+        let mut past_end = rsrc.clone();
+        put_u32(&mut past_end, 72, RSRC_VA + 0x10_0000);
+        assert_eq!(parse_version_info(&pe_image(false, &past_end)), None);
+
+        let mut before_section = rsrc.clone();
+        put_u32(&mut before_section, 72, RSRC_VA - 8);
+        assert_eq!(parse_version_info(&pe_image(false, &before_section)), None);
+
+        let mut huge_size = rsrc.clone();
+        put_u32(&mut huge_size, 76, u32::MAX);
+        assert_eq!(parse_version_info(&pe_image(false, &huge_size)), None);
+
+        let mut bad_subdir = rsrc;
+        put_u32(&mut bad_subdir, 20, 0x8000_0000 | 0x7FFF_0000);
+        assert_eq!(parse_version_info(&pe_image(false, &bad_subdir)), None);
+    }
+
+    #[test]
+    fn missing_rsrc_section_is_rejected() {
+        let mut img = sample_image(false);
+        // 资源目录指向任何节都不包含的 RVA。
+        let dir = 0x40 + 24 + 96 + IMAGE_DIRECTORY_ENTRY_RESOURCE * 8;
+        put_u32(&mut img, dir, 0x9000);
+        assert_eq!(parse_version_info(&img), None);
+
+        let mut no_dir = sample_image(true);
+        let dir = 0x40 + 24 + 112 + IMAGE_DIRECTORY_ENTRY_RESOURCE * 8;
+        put_u32(&mut no_dir, dir, 0);
+        assert_eq!(parse_version_info(&no_dir), None);
+    }
+
+    #[test]
+    fn odd_length_string_table_is_rejected() {
+        let strings = string("CompanyName", "Contoso Ltd.");
+        let mut version = version_info(&strings);
+        // StringTable 是最后一个块；把它的 wLength 改成奇数，字符串被截断在半个 WCHAR 上。
+        let table_len = block("040904b0", &[], 0, 1, &strings).len();
+        let table = version.len() - table_len;
+        put_u16(&mut version, table, table_len as u16 - 3);
+        assert_eq!(
+            parse_version_info(&pe_image(false, &resource_section(&version))),
+            None
+        );
+    }
+}
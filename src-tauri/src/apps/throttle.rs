@@ -0,0 +1,93 @@
+//! 后台扫描：按每秒文件数与字节数限速、暂停时等待，以及降低线程的 CPU 与 I/O 优先级。
+
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use super::{BackgroundOptions, CancelToken};
+
+/// 暂停或限速等待期间每隔这么久检查一次取消。
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 每批工作按限速折算成耗时，依次排在上一批之后；空闲或暂停过的时间不会攒成突发。
+/// 多个线程共用时合计速率受限。
+pub(crate) struct Throttle {
+    max_files_per_sec: u32,
+    max_bytes_per_sec: u64,
+    next: Mutex<Instant>,
+}
+
+impl Throttle {
+    pub(crate) fn new(options: &BackgroundOptions) -> Throttle {
+        Throttle {
+            max_files_per_sec: options.max_files_per_sec,
+            max_bytes_per_sec: options.max_bytes_per_sec,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// 每处理完一批文件调用：暂停时在此等待，超出限速时睡眠到这一批的配额用完为止。
+    pub(crate) fn pace(&self, files: u64, bytes: u64, cancel: &CancelToken) {
+        wait_while_paused(cancel);
+        let rate = |amount: u64, per_sec: u64| {
+            if per_sec == 0 {
+                0.0
+            } else {
+                amount as f64 / per_sec as f64
+            }
+        };
+        let cost =
+            rate(files, u64::from(self.max_files_per_sec)).max(rate(bytes, self.max_bytes_per_sec));
+        let due = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            *next = (*next).max(Instant::now()) + Duration::from_secs_f64(cost);
+            *next
+        };
+        sleep_until(due, cancel);
+    }
+}
+
+/// 暂停期间阻塞，恢复或取消后返回。
+pub(crate) fn wait_while_paused(cancel: &CancelToken) {
+    while cancel.is_paused() && !cancel.is_cancelled() {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn sleep_until(due: Instant, cancel: &CancelToken) {
+    loop {
+        let now = Instant::now();
+        if now >= due || cancel.is_cancelled() {
+            return;
+        }
+        std::thread::sleep((due - now).min(POLL_INTERVAL));
+    }
+}
+
+/// 当前线程进入后台模式，离开作用域时恢复。Windows 下同时降低 CPU 调度、
+/// I/O 与内存优先级，其他平台不做处理。
+pub(crate) struct BackgroundPriority(());
+
+impl BackgroundPriority {
+    pub(crate) fn enter() -> BackgroundPriority {
+        #[cfg(windows)]
+        set_thread_mode(windows_sys::Win32::System::Threading::THREAD_MODE_BACKGROUND_BEGIN);
+        BackgroundPriority(())
+    }
+}
+
+impl Drop for BackgroundPriority {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        set_thread_mode(windows_sys::Win32::System::Threading::THREAD_MODE_BACKGROUND_END);
+    }
+}
+
+#[cfg(windows)]
+fn set_thread_mode(mode: windows_sys::Win32::System::Threading::THREAD_PRIORITY) {
+    use windows_sys::Win32::System::Threading::{GetCurrentThread, SetThreadPriority};
+
+    // SAFETY: 伪句柄始终指向当前线程；失败只意味着保持原优先级。
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), mode);
+    }
+}
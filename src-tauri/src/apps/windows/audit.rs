@@ -1,22 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use super::matching::{build_app_tokens, build_candidate_folder_keys, score_folder, AppTokens};
-use super::roots::{build_roots, RootFolders};
-use super::sizing::directory_size;
-use super::uninstall::{dedupe_uninstall_entries, get_install_dir_hint, scan_uninstall_entries};
-use crate::apps::{AuditDuplicateInstallLocation, AuditOverview, AuditRootSummary, AuditUnassignedFolder};
+use super::history::AppHistoryEntry;
+use super::matching::{AssignedFolders, SharedFolder};
+use super::roots::{RootFolders, Roots};
+use super::size_index::now_secs;
+use super::sizing::{directory_size_cached, known_directory_size, SizeCache};
+use super::uninstall::{
+    command_executable, display_icon_path, get_install_dir_hint, UninstallEntry,
+};
+use super::ScanSnapshot;
+use crate::apps::{
+    AppRecord, AuditApp, AuditBrokenUninstallEntry, AuditDuplicateInstallLocation, AuditOptions,
+    AuditOrphanedApp, AuditOrphanedFolder, AuditOverview, AuditRelocatedFolder, AuditRootSummary,
+    AuditSkippedPath, AuditStaleApp, AuditUnassignedFolder, AuditUninstallIssue,
+};
 
-pub(super) fn audit_overview_windows() -> AuditOverview {
-    let mut uninstall = scan_uninstall_entries();
-    uninstall.sort_by(|a, b| a.name.cmp(&b.name));
-    uninstall = dedupe_uninstall_entries(uninstall);
-
-    let app_tokens = build_app_tokens(&uninstall);
-    let roots = build_roots();
+pub(super) fn audit_overview(snapshot: &ScanSnapshot, options: &AuditOptions) -> AuditOverview {
+    let uninstall = &snapshot.uninstall;
+    let roots = &snapshot.roots;
+    let assigned = &*snapshot.assigned();
+    let size_cache = &snapshot.size_cache;
 
     let mut unknown_program_size_count = 0u32;
     let mut install_dir_to_apps: HashMap<String, Vec<String>> = HashMap::new();
-    for u in &uninstall {
+    for u in uninstall {
         if u.estimated_bytes == 0 && get_install_dir_hint(u).is_none() {
             unknown_program_size_count = unknown_program_size_count.saturating_add(1);
         }
@@ -41,164 +49,568 @@ pub(super) fn audit_overview_windows() -> AuditOverview {
         })
         .collect();
     duplicate_install_locations.sort_by(|a, b| b.apps.len().cmp(&a.apps.len()));
+    let broken_uninstall_entries =
+        find_broken_uninstall_entries(uninstall, &duplicate_install_locations, size_cache);
 
-    let owners_local = build_owner_keys(roots.local.as_ref(), &app_tokens);
-    let owners_roaming = build_owner_keys(roots.roaming.as_ref(), &app_tokens);
-    let owners_local_low = build_owner_keys(roots.local_low.as_ref(), &app_tokens);
-    let owners_program_data = build_owner_keys(roots.program_data.as_ref(), &app_tokens);
-
+    // 各根目录在扫描时已整体遍历过，未关联文件夹的大小直接从缓存的子树汇总中取得。
     let mut unassigned_folders = Vec::new();
     let mut root_summaries = Vec::new();
-
-    if let Some(root) = roots.local.as_ref() {
-        let assigned = owners_local.len() as u32;
-        let unassigned = root
-            .folders
-            .keys()
-            .filter(|k| !owners_local.contains_key(*k))
-            .count() as u32;
-        root_summaries.push(AuditRootSummary {
-            kind: "appDataLocal".into(),
-            assigned_folders: assigned,
-            unassigned_folders: unassigned,
-        });
-        extend_unassigned_preview(&mut unassigned_folders, "appDataLocal", root, &owners_local);
-    }
-
-    if let Some(root) = roots.roaming.as_ref() {
-        let assigned = owners_roaming.len() as u32;
-        let unassigned = root
+    for (kind, root, owned) in root_assignments(roots, assigned) {
+        let Some(root) = root else {
+            continue;
+        };
+        let ownership = FolderOwnership::of(root, owned, &assigned.shared);
+        let mut unassigned: Vec<(&String, &PathBuf)> = root
             .folders
-            .keys()
-            .filter(|k| !owners_roaming.contains_key(*k))
-            .count() as u32;
+            .iter()
+            .filter(|(k, p)| !ownership.is_owned(k, p, size_cache))
+            .collect();
         root_summaries.push(AuditRootSummary {
-            kind: "appDataRoaming".into(),
-            assigned_folders: assigned,
-            unassigned_folders: unassigned,
+            kind: kind.into(),
+            assigned_folders: (root.folders.len() - unassigned.len()) as u32,
+            unassigned_folders: unassigned.len() as u32,
+            skipped_paths: 0,
         });
-        extend_unassigned_preview(&mut unassigned_folders, "appDataRoaming", root, &owners_roaming);
+        unassigned.sort();
+        unassigned_folders.extend(unassigned.into_iter().take(80).map(|(k, p)| {
+            AuditUnassignedFolder {
+                kind: kind.into(),
+                folder: k.clone(),
+                path: p.to_string_lossy().to_string(),
+                bytes: ownership.unowned_bytes(k, p, size_cache),
+            }
+        }));
     }
 
-    if let Some(root) = roots.local_low.as_ref() {
-        let assigned = owners_local_low.len() as u32;
-        let unassigned = root
-            .folders
-            .keys()
-            .filter(|k| !owners_local_low.contains_key(*k))
-            .count() as u32;
-        root_summaries.push(AuditRootSummary {
-            kind: "appDataLocalLow".into(),
-            assigned_folders: assigned,
-            unassigned_folders: unassigned,
-        });
-        extend_unassigned_preview(
-            &mut unassigned_folders,
-            "appDataLocalLow",
-            root,
-            &owners_local_low,
-        );
-    }
+    let stale_apps = find_stale_apps(&snapshot.records(), options, now_secs());
 
-    if let Some(root) = roots.program_data.as_ref() {
-        let assigned = owners_program_data.len() as u32;
-        let unassigned = root
-            .folders
-            .keys()
-            .filter(|k| !owners_program_data.contains_key(*k))
+    // 扫描已遍历全部根目录与安装目录，此时的跳过记录是完整的。
+    let mut skipped_paths = collect_skipped_paths(roots, size_cache);
+    for summary in &mut root_summaries {
+        summary.skipped_paths = skipped_paths
+            .iter()
+            .filter(|s| s.kind == summary.kind)
             .count() as u32;
-        root_summaries.push(AuditRootSummary {
-            kind: "programData".into(),
-            assigned_folders: assigned,
-            unassigned_folders: unassigned,
-        });
-        extend_unassigned_preview(
-            &mut unassigned_folders,
-            "programData",
-            root,
-            &owners_program_data,
-        );
     }
+    let skipped_path_count = skipped_paths.len() as u32;
+    skipped_paths.truncate(200);
+    let relocated_folders = collect_relocated_folders(roots, size_cache);
+    let orphaned_apps = find_orphaned_apps(&snapshot.history, roots, assigned, size_cache);
 
     unassigned_folders.sort_by(|a, b| a.path.cmp(&b.path));
     unassigned_folders.truncate(200);
 
+    let apps = uninstall
+        .iter()
+        .map(|u| AuditApp {
+            id: u.id.clone(),
+            name: u.name.clone(),
+        })
+        .collect();
+
     AuditOverview {
         app_count: uninstall.len() as u32,
         unknown_program_size_count,
         roots: root_summaries,
         duplicate_install_locations,
         unassigned_folders,
+        stale_apps,
+        skipped_paths,
+        skipped_path_count,
+        relocated_folders,
+        orphaned_apps,
+        broken_uninstall_entries,
+        apps,
     }
 }
 
-fn extend_unassigned_preview(
-    out: &mut Vec<AuditUnassignedFolder>,
+/// 某个根目录下顶层文件夹的大小，`kind` 与审计报告中的根目录类型相同。
+pub(super) fn measure_folder_size(
+    snapshot: &ScanSnapshot,
     kind: &str,
-    root: &RootFolders,
-    owners: &HashMap<String, String>,
-) {
-    let mut keys: Vec<&String> = root.folders.keys().filter(|k| !owners.contains_key(*k)).collect();
-    keys.sort();
-    for k in keys.into_iter().take(80) {
-        if let Some(p) = root.folders.get(k) {
-            out.push(AuditUnassignedFolder {
-                kind: kind.into(),
-                folder: k.clone(),
-                path: p.to_string_lossy().to_string(),
-            });
-        }
-    }
+    folder: &str,
+) -> Result<u64, String> {
+    let assigned = snapshot.assigned();
+    let (root, owned) = root_assignments(&snapshot.roots, &assigned)
+        .into_iter()
+        .find(|(k, _, _)| *k == kind)
+        .and_then(|(_, root, owned)| Some((root?, owned)))
+        .ok_or_else(|| format!("根目录 {kind} 不存在"))?;
+    let key = folder.to_lowercase();
+    let path = root
+        .folders
+        .get(&key)
+        .ok_or_else(|| format!("{kind} 下没有文件夹 {folder}"))?;
+    let ownership = FolderOwnership::of(root, owned, &assigned.shared);
+    Ok(ownership.unowned_bytes(&key, path, &snapshot.size_cache))
 }
 
-fn build_owner_keys(root: Option<&RootFolders>, tokens: &[AppTokens]) -> HashMap<String, String> {
-    let Some(root) = root else {
-        return HashMap::new();
-    };
+/// 根目录类型、根目录及扫描时归属到其中的独占文件夹。
+type RootAssignment<'a> = (
+    &'static str,
+    Option<&'a RootFolders>,
+    &'a HashMap<String, Vec<PathBuf>>,
+);
 
-    let mut owners: HashMap<String, (i32, String)> = HashMap::new();
+fn root_assignments<'a>(
+    roots: &'a Roots,
+    assigned: &'a AssignedFolders,
+) -> [RootAssignment<'a>; 4] {
+    [
+        ("appDataLocal", roots.local.as_ref(), &assigned.local),
+        ("appDataRoaming", roots.roaming.as_ref(), &assigned.roaming),
+        (
+            "appDataLocalLow",
+            roots.local_low.as_ref(),
+            &assigned.local_low,
+        ),
+        (
+            "programData",
+            roots.program_data.as_ref(),
+            &assigned.program_data,
+        ),
+    ]
+}
 
-    for app in tokens {
-        let candidates = build_candidate_folder_keys(app);
-        for c in candidates {
-            if !root.folders.contains_key(&c) {
+/// 根目录下顶层文件夹的归属，直接取自扫描的归属结果，两者不会不一致。
+struct FolderOwnership {
+    /// 整体归属（独占或共享）的文件夹名。
+    whole: HashSet<String>,
+    /// 只有部分子路径被归属的文件夹（如签名库认领的 `Google\Chrome\User Data`）及这些子路径。
+    partial: HashMap<String, Vec<PathBuf>>,
+}
+
+impl FolderOwnership {
+    fn of(
+        root: &RootFolders,
+        owned: &HashMap<String, Vec<PathBuf>>,
+        shared: &[SharedFolder],
+    ) -> FolderOwnership {
+        let mut whole = HashSet::new();
+        let mut partial: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for p in owned
+            .values()
+            .flatten()
+            .chain(shared.iter().map(|s| &s.path))
+        {
+            let Ok(rel) = p.strip_prefix(&root.path) else {
                 continue;
-            }
-            let score = score_folder(&c, app);
-            if score <= 0 {
+            };
+            let mut components = rel.components();
+            let Some(first) = components.next() else {
+                continue;
+            };
+            let key = first.as_os_str().to_string_lossy().to_lowercase();
+            if !root.folders.contains_key(&key) {
                 continue;
             }
-            match owners.get(&c) {
-                Some((best_score, _)) if *best_score >= score => {}
-                _ => {
-                    owners.insert(c, (score, app.app_id.clone()));
-                }
+            if components.next().is_none() {
+                whole.insert(key);
+            } else {
+                partial.entry(key).or_default().push(p.clone());
             }
         }
+        partial.retain(|k, _| !whole.contains(k));
+        for paths in partial.values_mut() {
+            paths.sort();
+            paths.dedup();
+        }
+        FolderOwnership { whole, partial }
+    }
+
+    /// 文件夹中没有归属到任何软件的部分的大小。
+    fn unowned_bytes(&self, key: &str, path: &Path, size_cache: &SizeCache) -> u64 {
+        if self.whole.contains(key) {
+            return 0;
+        }
+        let owned: u64 = self
+            .partial
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|p| directory_size_cached(p, size_cache).apparent)
+            .sum();
+        directory_size_cached(path, size_cache)
+            .apparent
+            .saturating_sub(owned)
     }
 
-    owners.into_iter().map(|(k, (_s, id))| (k, id)).collect()
+    /// 整体归属，或已归属的子路径覆盖了全部内容。
+    fn is_owned(&self, key: &str, path: &Path, size_cache: &SizeCache) -> bool {
+        self.whole.contains(key)
+            || (self.partial.contains_key(key) && self.unowned_bytes(key, path, size_cache) == 0)
+    }
 }
 
-pub(super) fn measure_folder_size_windows(kind: &str, folder: &str) -> u64 {
-    let roots = build_roots();
-    let folder_key = folder.to_ascii_lowercase();
+/// 按所在根目录归类；不在任何根目录下的是安装目录。
+fn root_kind_of(roots: &Roots, path: &Path) -> &'static str {
+    [
+        ("appDataLocal", roots.local.as_ref()),
+        ("appDataRoaming", roots.roaming.as_ref()),
+        ("appDataLocalLow", roots.local_low.as_ref()),
+        ("programData", roots.program_data.as_ref()),
+    ]
+    .into_iter()
+    .find(|(_, root)| root.is_some_and(|r| path.starts_with(&r.path)))
+    .map_or("program", |(kind, _)| kind)
+}
 
-    let root = match kind {
-        "appDataLocal" => roots.local.as_ref(),
-        "appDataRoaming" => roots.roaming.as_ref(),
-        "appDataLocalLow" => roots.local_low.as_ref(),
-        "programData" => roots.program_data.as_ref(),
-        _ => None,
-    };
+fn collect_skipped_paths(roots: &Roots, size_cache: &SizeCache) -> Vec<AuditSkippedPath> {
+    size_cache
+        .skipped_paths()
+        .into_iter()
+        .map(|(path, reason)| AuditSkippedPath {
+            kind: root_kind_of(roots, &path).into(),
+            path: path.to_string_lossy().to_string(),
+            reason: reason.kind().into(),
+            label: reason.label().into(),
+        })
+        .collect()
+}
 
-    let Some(root) = root else {
-        return 0;
-    };
+/// 链接本身所在的根目录决定归类，目标可能在任何卷上。
+fn collect_relocated_folders(roots: &Roots, size_cache: &SizeCache) -> Vec<AuditRelocatedFolder> {
+    size_cache
+        .relocated_folders()
+        .into_iter()
+        .map(|f| AuditRelocatedFolder {
+            kind: root_kind_of(roots, Path::new(&f.path)).into(),
+            path: f.path,
+            target: f.target,
+            volume: f.volume,
+        })
+        .collect()
+}
+
+const SECS_PER_MONTH: u64 = 30 * 24 * 60 * 60;
+
+/// 总占用不小于阈值、且所有目录在最近 N 个月内都没有修改或访问记录的软件。
+/// 直接使用扫描（及其后的文件监视）得到的记录，不再重新统计；读不到任何时间的软件无法判断，不列出。
+fn find_stale_apps(
+    records: &HashMap<String, AppRecord>,
+    options: &AuditOptions,
+    now: u64,
+) -> Vec<AuditStaleApp> {
+    let cutoff = now.saturating_sub(u64::from(options.stale_months) * SECS_PER_MONTH);
+    let mut out: Vec<AuditStaleApp> = records
+        .values()
+        .filter(|r| r.total_bytes >= options.stale_min_bytes)
+        .filter_map(|r| {
+            let last_activity = r.last_activity.filter(|t| *t < cutoff)?;
+            Some(AuditStaleApp {
+                id: r.id.clone(),
+                name: r.name.clone(),
+                total_bytes: r.total_bytes,
+                last_activity,
+            })
+        })
+        .collect();
+    out.sort_by_key(|a| (std::cmp::Reverse(a.total_bytes), a.name.clone()));
+    out
+}
+
+/// 登记大小与实际大小相差至少这么多倍、且差值不小于下面的字节数时视为明显不符。
+const SIZE_MISMATCH_RATIO: u64 = 10;
+const SIZE_MISMATCH_MIN_BYTES: u64 = 64 * 1024 * 1024;
 
-    let Some(path) = root.folders.get(&folder_key) else {
-        return 0;
+/// 检查卸载条目登记的安装目录、卸载程序与图标是否存在，以及登记大小是否可信。
+/// 这类条目会残留在「应用和功能」中，且往往无法正常卸载。
+fn find_broken_uninstall_entries(
+    uninstall: &[UninstallEntry],
+    shared_install_dirs: &[AuditDuplicateInstallLocation],
+    size_cache: &SizeCache,
+) -> Vec<AuditBrokenUninstallEntry> {
+    let issue = |reason: &str, label: &str, path: &Path| AuditUninstallIssue {
+        reason: reason.into(),
+        label: label.into(),
+        path: path.to_string_lossy().to_string(),
+        estimated_bytes: None,
+        measured_bytes: None,
     };
 
-    directory_size(path)
+    let mut out = Vec::new();
+    for u in uninstall {
+        let mut issues = Vec::new();
+        let install_dir = u.install_location.as_deref().map(Path::new);
+        if let Some(dir) = install_dir.filter(|d| !d.is_dir()) {
+            issues.push(issue("installLocationMissing", "安装目录不存在", dir));
+        }
+        if let Some(exe) = u
+            .uninstall_string
+            .as_deref()
+            .and_then(command_executable)
+            .filter(|p| !p.is_file())
+        {
+            issues.push(issue("uninstallerMissing", "卸载程序不存在", &exe));
+        }
+        if let Some(icon) = u
+            .display_icon
+            .as_deref()
+            .and_then(display_icon_path)
+            .filter(|p| !p.exists())
+        {
+            issues.push(issue("iconMissing", "图标文件不存在", &icon));
+        }
+
+        // 多个软件共用或层级过浅的目录（如 `C:\Program Files` 本身）不代表单个软件的大小。
+        // 审计不遍历目录，只比较本次会话中已统计过的安装目录。
+        let measurable = install_dir.filter(|d| {
+            d.components().count() >= 4
+                && !shared_install_dirs
+                    .iter()
+                    .any(|s| s.install_dir == d.to_string_lossy().to_ascii_lowercase())
+        });
+        let measured = measurable
+            .filter(|_| u.estimated_bytes > 0)
+            .and_then(|d| Some((d, known_directory_size(d, size_cache)?.apparent)));
+        if let Some((dir, measured)) = measured {
+            let (small, large) = if measured < u.estimated_bytes {
+                (measured, u.estimated_bytes)
+            } else {
+                (u.estimated_bytes, measured)
+            };
+            if large - small >= SIZE_MISMATCH_MIN_BYTES
+                && large >= small.saturating_mul(SIZE_MISMATCH_RATIO)
+            {
+                issues.push(AuditUninstallIssue {
+                    estimated_bytes: Some(u.estimated_bytes),
+                    measured_bytes: Some(measured),
+                    ..issue("sizeMismatch", "登记大小与实际明显不符", dir)
+                });
+            }
+        }
+
+        if !issues.is_empty() {
+            out.push(AuditBrokenUninstallEntry {
+                id: u.id.clone(),
+                name: u.name.clone(),
+                registry_keys: u.registry_keys.clone(),
+                issues,
+            });
+        }
+    }
+    out
+}
+
+/// 已卸载软件留下的文件夹；现在归属到其他软件的、以及记录之后已被删除的不算残留。
+fn find_orphaned_apps(
+    history: &[AppHistoryEntry],
+    roots: &Roots,
+    assigned: &AssignedFolders,
+    size_cache: &SizeCache,
+) -> Vec<AuditOrphanedApp> {
+    let owned: HashSet<String> = root_assignments(roots, assigned)
+        .into_iter()
+        .flat_map(|(_, _, owned)| owned.values().flatten())
+        .chain(assigned.extra.values().flatten())
+        .chain(assigned.shared.iter().map(|s| &s.path))
+        .map(|p| p.to_string_lossy().to_lowercase())
+        .collect();
+
+    let mut out: Vec<AuditOrphanedApp> = history
+        .iter()
+        .filter_map(|e| {
+            let uninstalled_at = e.removed_at?;
+            let folders: Vec<AuditOrphanedFolder> = e
+                .folders
+                .iter()
+                .filter(|p| !owned.contains(&p.to_string_lossy().to_lowercase()))
+                .filter(|p| p.exists())
+                .map(|p| AuditOrphanedFolder {
+                    kind: root_kind_of(roots, p).into(),
+                    path: p.to_string_lossy().to_string(),
+                    bytes: directory_size_cached(p, size_cache).apparent,
+                })
+                .collect();
+            if folders.is_empty() {
+                return None;
+            }
+            Some(AuditOrphanedApp {
+                id: e.id.clone(),
+                name: e.name.clone(),
+                publisher: e.publisher.clone(),
+                last_seen: e.last_seen,
+                uninstalled_at,
+                total_bytes: folders.iter().map(|f| f.bytes).sum(),
+                folders,
+            })
+        })
+        .collect();
+    out.sort_by_key(|a| std::cmp::Reverse(a.total_bytes));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::windows::sizing::SizeCache;
+
+    const MIB: u64 = 1024 * 1024;
+    const GIB: u64 = 1024 * MIB;
+    const NOW: u64 = 1_700_000_000;
+
+    fn record(id: &str, total_bytes: u64, last_activity: Option<u64>) -> AppRecord {
+        AppRecord {
+            id: id.to_string(),
+            name: id.to_string(),
+            publisher: None,
+            vendor_id: None,
+            vendor_name: None,
+            total_bytes,
+            total_allocated_bytes: total_bytes,
+            breakdown: Vec::new(),
+            last_activity,
+            error_bytes: None,
+            incomplete: false,
+            followed_volumes: Vec::new(),
+            estimated: false,
+        }
+    }
+
+    fn records(list: Vec<AppRecord>) -> HashMap<String, AppRecord> {
+        list.into_iter().map(|r| (r.id.clone(), r)).collect()
+    }
+
+    #[test]
+    fn stale_apps_need_both_size_and_age() {
+        let months_ago = |m: u64| Some(NOW - m * SECS_PER_MONTH);
+        let records = records(vec![
+            record("old-large", 3 * GIB, months_ago(12)),
+            record("old-larger", 5 * GIB, months_ago(7)),
+            record("old-small", GIB - 1, months_ago(12)),
+            record("recent-large", 3 * GIB, months_ago(5)),
+            record("no-activity", 3 * GIB, None),
+        ]);
+        let stale = find_stale_apps(&records, &AuditOptions::default(), NOW);
+        let ids: Vec<&str> = stale.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["old-larger", "old-large"]);
+        assert_eq!(stale[1].last_activity, NOW - 12 * SECS_PER_MONTH);
+    }
+
+    #[test]
+    fn stale_threshold_follows_options() {
+        let cutoff = NOW - 3 * SECS_PER_MONTH;
+        let records = records(vec![
+            record("at-cutoff", GIB, Some(cutoff)),
+            record("before-cutoff", GIB, Some(cutoff - 1)),
+        ]);
+        let options = AuditOptions {
+            stale_months: 3,
+            stale_min_bytes: GIB,
+        };
+        let stale = find_stale_apps(&records, &options, NOW);
+        let ids: Vec<&str> = stale.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["before-cutoff"]);
+    }
+
+    fn history_entry(id: &str, removed_at: Option<u64>, folders: Vec<PathBuf>) -> AppHistoryEntry {
+        AppHistoryEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            publisher: None,
+            app_key: id.to_string(),
+            last_seen: NOW - 100,
+            removed_at,
+            folders,
+        }
+    }
+
+    #[test]
+    fn orphaned_apps_skip_deleted_and_reassigned_folders() {
+        let dir = std::env::temp_dir().join(format!("appmanager-orphans-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let (left, gone, taken) = (dir.join("Left"), dir.join("Gone"), dir.join("Taken"));
+        std::fs::create_dir_all(&left).unwrap();
+        std::fs::create_dir_all(&taken).unwrap();
+        std::fs::write(left.join("data.bin"), [0u8; 1000]).unwrap();
+        let roots = Roots {
+            local: Some(RootFolders {
+                path: dir.clone(),
+                folders: HashMap::new(),
+            }),
+            roaming: None,
+            local_low: None,
+            program_data: None,
+        };
+        let mut assigned = AssignedFolders::default();
+        assigned.extra.insert("other".into(), vec![taken.clone()]);
+        let history = vec![
+            history_entry(
+                "removed",
+                Some(NOW),
+                vec![left.clone(), gone.clone(), taken],
+            ),
+            history_entry("all-deleted", Some(NOW), vec![gone]),
+            history_entry("installed", None, vec![left.clone()]),
+        ];
+
+        let orphans = find_orphaned_apps(&history, &roots, &assigned, &SizeCache::default());
+        assert_eq!(orphans.len(), 1);
+        let app = &orphans[0];
+        assert_eq!(app.id, "removed");
+        assert_eq!(app.uninstalled_at, NOW);
+        assert_eq!(app.folders.len(), 1);
+        assert_eq!(app.folders[0].path, left.to_string_lossy());
+        assert_eq!(app.folders[0].kind, "appDataLocal");
+        assert_eq!(app.total_bytes, 1000);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn installed(id: &str, estimated_bytes: u64, dir: &Path) -> UninstallEntry {
+        UninstallEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            publisher: None,
+            estimated_bytes,
+            install_location: Some(dir.to_string_lossy().to_string()),
+            display_icon: None,
+            uninstall_string: None,
+            registry_keys: Vec::new(),
+        }
+    }
+
+    /// 稀疏文件：表观大小为 `bytes`，不实际写入数据。
+    fn install_dir(parent: &Path, name: &str, bytes: u64) -> PathBuf {
+        let dir = parent.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = std::fs::File::create(dir.join("app.bin")).unwrap();
+        file.set_len(bytes).unwrap();
+        dir
+    }
+
+    #[test]
+    fn size_mismatch_needs_measured_dir_ratio_and_minimum_gap() {
+        let parent =
+            std::env::temp_dir().join(format!("appmanager-mismatch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&parent);
+        let small = install_dir(&parent, "Small", MIB);
+        let large = install_dir(&parent, "Large", 100 * MIB);
+        let unmeasured = install_dir(&parent, "Unmeasured", 100 * MIB);
+        let cache = SizeCache::default();
+        directory_size_cached(&small, &cache);
+        directory_size_cached(&large, &cache);
+
+        let uninstall = vec![
+            // 相差 1000 倍，但差值不足 64 MiB。
+            installed("below-minimum", 1024, &small),
+            // 差值 80 MiB，但只差 5 倍。
+            installed("ratio-not-reached", 20 * MIB, &large),
+            // 审计不遍历目录，未统计过的不比较。
+            installed("unmeasured", 1024, &unmeasured),
+            installed("understated", MIB, &large),
+            installed("overstated", 2048 * MIB, &small),
+        ];
+        let broken = find_broken_uninstall_entries(&uninstall, &[], &cache);
+        let ids: Vec<&str> = broken.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["understated", "overstated"]);
+        for b in &broken {
+            assert_eq!(b.issues.len(), 1);
+            assert_eq!(b.issues[0].reason, "sizeMismatch");
+        }
+        let issue = &broken[0].issues[0];
+        assert_eq!(issue.estimated_bytes, Some(MIB));
+        assert_eq!(issue.measured_bytes, Some(100 * MIB));
+        assert_eq!(issue.path, large.to_string_lossy());
+        let _ = std::fs::remove_dir_all(&parent);
+    }
 }
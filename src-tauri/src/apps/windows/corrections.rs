@@ -0,0 +1,231 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::matching::{build_app_tokens, is_stop_token_name, AppTokens};
+use super::storage::{read_json, write_json};
+use super::uninstall::UninstallEntry;
+
+const CORRECTIONS_FILE: &str = "corrections.json";
+const CORRECTIONS_VERSION: u32 = 1;
+
+/// 用户在审计面板中手动指定的「文件夹名 → 软件」归属。
+/// 只记录与机器无关的标识（文件夹 key、软件名 key、厂商 id），以便在团队内共享。
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Correction {
+    pub(super) root: String,
+    pub(super) folder_key: String,
+    pub(super) app_key: String,
+    pub(super) app_name: String,
+    #[serde(default)]
+    pub(super) vendor_id: Option<String>,
+    #[serde(default)]
+    pub(super) name_tokens: Vec<String>,
+    #[serde(default)]
+    pub(super) publisher_tokens: Vec<String>,
+    #[serde(default)]
+    pub(super) created_at: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CorrectionsFile {
+    version: u32,
+    corrections: Vec<Correction>,
+}
+
+impl Correction {
+    /// 把 `kind` 根目录下的 `folder` 归属到 `app` 的修正。
+    pub(super) fn for_app(kind: &str, folder: &str, app: AppTokens, created_at: u64) -> Correction {
+        Correction {
+            root: kind.to_string(),
+            folder_key: folder.to_lowercase(),
+            app_key: app.app_key,
+            app_name: app.app_name,
+            vendor_id: app.vendor_id,
+            name_tokens: app.name_tokens,
+            publisher_tokens: app.publisher_tokens,
+            created_at,
+        }
+    }
+
+    /// 软件标识一致（规范名相同，或同一厂商且厂商名以外的名称 token 有交集）时认为指向同一软件。
+    /// 同厂商的软件名大多带着厂商名（Google Chrome / Google Drive），只比较其余 token，
+    /// 避免一条修正把厂商的所有软件都归到这个文件夹。
+    pub(super) fn matches_app(&self, app: &AppTokens) -> bool {
+        if !self.app_key.is_empty() && self.app_key == app.app_key {
+            return true;
+        }
+        if self.vendor_id.is_none() || self.vendor_id != app.vendor_id {
+            return false;
+        }
+        let vendor = self.vendor_id.as_deref();
+        let theirs: Vec<&String> =
+            distinctive_tokens(&app.name_tokens, &app.publisher_tokens, vendor).collect();
+        distinctive_tokens(&self.name_tokens, &self.publisher_tokens, vendor)
+            .any(|t| theirs.contains(&t))
+    }
+}
+
+fn distinctive_tokens<'a>(
+    name_tokens: &'a [String],
+    publisher_tokens: &'a [String],
+    vendor_id: Option<&'a str>,
+) -> impl Iterator<Item = &'a String> {
+    name_tokens.iter().filter(move |t| {
+        Some(t.as_str()) != vendor_id && !publisher_tokens.contains(t) && !is_stop_token_name(t)
+    })
+}
+
+/// 扫描时读取；版本不符的文件与读取失败一样视为没有修正。
+pub(super) fn load_corrections() -> Vec<Correction> {
+    read_corrections().unwrap_or_default()
+}
+
+/// 写入前读取：文件来自其他版本时报错，而不是用当前版本的格式覆盖它。
+fn read_corrections() -> Result<Vec<Correction>, String> {
+    read_json::<CorrectionsFile>(CORRECTIONS_FILE).map_or(Ok(Vec::new()), checked_corrections)
+}
+
+/// 目前只有一个版本，没有可迁移的旧格式；其他版本（更新的应用写入的）一律拒绝。
+fn checked_corrections(file: CorrectionsFile) -> Result<Vec<Correction>, String> {
+    if file.version != CORRECTIONS_VERSION {
+        return Err(format!(
+            "不支持的修正集版本 {}，当前版本为 {CORRECTIONS_VERSION}",
+            file.version
+        ));
+    }
+    Ok(file.corrections)
+}
+
+fn save_corrections(corrections: Vec<Correction>) -> Result<(), String> {
+    write_json(
+        CORRECTIONS_FILE,
+        &CorrectionsFile {
+            version: CORRECTIONS_VERSION,
+            corrections,
+        },
+    )
+}
+
+/// 同一「文件夹 key + 软件 key」只保留最新的一条。
+fn merge(existing: &mut Vec<Correction>, incoming: Correction) -> bool {
+    match existing
+        .iter_mut()
+        .find(|c| c.folder_key == incoming.folder_key && c.app_key == incoming.app_key)
+    {
+        Some(c) => {
+            *c = incoming;
+            false
+        }
+        None => {
+            existing.push(incoming);
+            true
+        }
+    }
+}
+
+/// `entry` 取自扫描快照，Publisher 已按版本资源补全，与扫描时的匹配依据一致。
+pub(super) fn record_correction(
+    kind: &str,
+    folder: &str,
+    entry: &UninstallEntry,
+) -> Result<(), String> {
+    let app = build_app_tokens(std::slice::from_ref(entry)).remove(0);
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut corrections = read_corrections()?;
+    merge(
+        &mut corrections,
+        Correction::for_app(kind, folder, app, created_at),
+    );
+    save_corrections(corrections)
+}
+
+pub(super) fn export_corrections() -> String {
+    serde_json::to_string_pretty(&CorrectionsFile {
+        version: CORRECTIONS_VERSION,
+        corrections: load_corrections(),
+    })
+    .unwrap_or_default()
+}
+
+/// 合并导入的修正集，返回新增条数。
+pub(super) fn import_corrections(json: &str) -> Result<u32, String> {
+    let incoming = checked_corrections(serde_json::from_str(json).map_err(|e| e.to_string())?)?;
+    let mut corrections = read_corrections()?;
+    let added = incoming
+        .into_iter()
+        .filter(|c| !c.folder_key.is_empty() && !c.app_key.is_empty())
+        .filter(|c| merge(&mut corrections, c.clone()))
+        .count() as u32;
+    save_corrections(corrections)?;
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, publisher: &str) -> AppTokens {
+        let entry = UninstallEntry {
+            id: name.to_string(),
+            name: name.to_string(),
+            publisher: Some(publisher.to_string()),
+            estimated_bytes: 0,
+            install_location: None,
+            display_icon: None,
+            uninstall_string: None,
+            registry_keys: Vec::new(),
+        };
+        build_app_tokens(&[entry]).remove(0)
+    }
+
+    fn correction_for(app: AppTokens) -> Correction {
+        Correction::for_app("appDataLocal", "folder", app, 0)
+    }
+
+    #[test]
+    fn same_app_key_matches_across_versions() {
+        let c = correction_for(app("Foo Studio 2023", "Foo Inc."));
+        assert!(c.matches_app(&app("Foo Studio 2024", "Foo Inc.")));
+    }
+
+    #[test]
+    fn vendor_name_alone_does_not_match_sibling_apps() {
+        let c = correction_for(app("Google Chrome", "Google LLC"));
+        assert!(!c.matches_app(&app("Google Drive", "Google LLC")));
+
+        let c = correction_for(app("Microsoft Edge", "Microsoft Corporation"));
+        assert!(!c.matches_app(&app("Microsoft Teams", "Microsoft Corporation")));
+    }
+
+    #[test]
+    fn shared_product_token_within_vendor_matches() {
+        let c = correction_for(app("Adobe Acrobat Reader", "Adobe Inc."));
+        assert!(c.matches_app(&app("Adobe Acrobat DC", "Adobe Systems Incorporated")));
+    }
+
+    #[test]
+    fn imports_from_other_versions_are_rejected() {
+        let json = |version: u32| {
+            format!(
+                r#"{{"version":{version},"corrections":[{{"root":"appDataLocal","folderKey":"foo","appKey":"foo","appName":"Foo"}}]}}"#
+            )
+        };
+        let parse = |json: &str| checked_corrections(serde_json::from_str(json).unwrap());
+        assert_eq!(parse(&json(CORRECTIONS_VERSION)).unwrap().len(), 1);
+        assert!(parse(&json(CORRECTIONS_VERSION + 1)).is_err());
+        // 版本不符时在读写本地文件之前就返回。
+        assert!(import_corrections(&json(CORRECTIONS_VERSION + 1)).is_err());
+    }
+
+    #[test]
+    fn product_token_from_another_vendor_does_not_match() {
+        let c = correction_for(app("Acme Player", "Acme Corp"));
+        assert!(!c.matches_app(&app("Other Player", "Other Media")));
+    }
+}
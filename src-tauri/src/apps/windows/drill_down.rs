@@ -0,0 +1,225 @@
+use std::path::{Component, Path, PathBuf};
+
+use super::sizing::{directory_size_cached, SizeCache};
+use super::uninstall::get_install_dir_hint;
+use super::ScanSnapshot;
+use crate::apps::disk_usage::file_usage;
+use crate::apps::{DrillDownEntry, DrillDownLevel};
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// 逐层浏览某个软件明细条目下的目录：`path` 为空时列出该条目的全部目录，
+/// 否则列出 `path` 的直接子项。子目录大小取自扫描时的子树缓存，文件大小当场读取。
+pub(super) fn drill_down(
+    snapshot: &ScanSnapshot,
+    app_id: &str,
+    kind: &str,
+    path: Option<&str>,
+    limit: Option<u32>,
+) -> Result<DrillDownLevel, String> {
+    let roots = breakdown_paths(snapshot, app_id, kind)?;
+    if roots.is_empty() {
+        return Err(format!("条目 {kind} 没有可浏览的目录"));
+    }
+    let cache = &snapshot.size_cache;
+
+    let (mut entries, parent) = match path {
+        None => (roots.iter().map(|p| dir_entry(p, cache)).collect(), None),
+        Some(p) => {
+            let dir = PathBuf::from(p);
+            if !is_within(&dir, &roots) {
+                return Err(format!("{p} 不在该条目的目录内"));
+            }
+            let parent = if roots.contains(&dir) {
+                None
+            } else {
+                dir.parent().map(|d| d.to_string_lossy().to_string())
+            };
+            (list_children(&dir, cache), parent)
+        }
+    };
+
+    entries.sort_by_key(|e| std::cmp::Reverse(e.bytes));
+    let limit = limit.map(|n| n as usize).unwrap_or(DEFAULT_LIMIT).max(1);
+    let rest = entries.split_off(entries.len().min(limit));
+
+    Ok(DrillDownLevel {
+        path: path.map(str::to_string),
+        parent,
+        entries,
+        remaining_count: rest.len() as u32,
+        remaining_bytes: rest.iter().map(|e| e.bytes).sum(),
+    })
+}
+
+/// `starts_with` 按路径分量比较，`<root>\..\..` 也会通过；含 `..` 的路径一律拒绝，
+/// 只能浏览条目目录之内。
+fn is_within(dir: &Path, roots: &[PathBuf]) -> bool {
+    !dir.components().any(|c| c == Component::ParentDir) && roots.iter().any(|r| dir.starts_with(r))
+}
+
+/// 可浏览的明细类型，与 `AppBreakdownEntry.kind` 相同。
+pub(super) const BREAKDOWN_KINDS: [&str; 7] = [
+    "program",
+    "appDataLocal",
+    "appDataRoaming",
+    "appDataLocalLow",
+    "programData",
+    "extraData",
+    "shared",
+];
+
+/// 与 `enrich_with_breakdown` 中各条目对应的目录。
+pub(super) fn breakdown_paths(
+    snapshot: &ScanSnapshot,
+    app_id: &str,
+    kind: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let app = snapshot
+        .uninstall
+        .iter()
+        .find(|u| u.id == app_id)
+        .ok_or_else(|| format!("未找到软件 {app_id}"))?;
+    let assigned = snapshot.assigned();
+    let owned = |map: &std::collections::HashMap<String, Vec<PathBuf>>| {
+        map.get(app_id).cloned().unwrap_or_default()
+    };
+
+    let paths = match kind {
+        "program" => get_install_dir_hint(app).into_iter().collect(),
+        "appDataLocal" => owned(&assigned.local),
+        "appDataRoaming" => owned(&assigned.roaming),
+        "appDataLocalLow" => owned(&assigned.local_low),
+        "programData" => owned(&assigned.program_data),
+        "extraData" => owned(&assigned.extra),
+        "shared" => assigned
+            .shared
+            .iter()
+            .filter(|f| f.owners.iter().any(|o| o.app_id == app_id))
+            .map(|f| f.path.clone())
+            .collect(),
+        _ => return Err(format!("未知的明细类型 {kind}")),
+    };
+    Ok(paths)
+}
+
+fn dir_entry(dir: &Path, cache: &SizeCache) -> DrillDownEntry {
+    let size = directory_size_cached(dir, cache);
+    DrillDownEntry {
+        name: dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| dir.to_string_lossy().to_string()),
+        path: dir.to_string_lossy().to_string(),
+        is_dir: true,
+        bytes: size.apparent,
+        allocated_bytes: size.allocated,
+    }
+}
+
+fn list_children(dir: &Path, cache: &SizeCache) -> Vec<DrillDownEntry> {
+    let Ok(rd) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    for e in rd.flatten() {
+        let Ok(t) = e.file_type() else {
+            continue;
+        };
+        let path = e.path();
+        if t.is_dir() {
+            out.push(dir_entry(&path, cache));
+        } else if t.is_file() {
+            let Ok(meta) = e.metadata() else {
+                continue;
+            };
+            let usage = file_usage(&path, &meta);
+            out.push(DrillDownEntry {
+                name: e.file_name().to_string_lossy().to_string(),
+                path: path.to_string_lossy().to_string(),
+                is_dir: false,
+                bytes: usage.apparent,
+                allocated_bytes: usage.allocated,
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::windows::matching::{AssignedFolders, SharedFolder, SharedOwner};
+    use crate::apps::windows::roots::Roots;
+    use crate::apps::windows::uninstall::UninstallEntry;
+    use crate::apps::ScanOptions;
+    use std::sync::RwLock;
+
+    fn snapshot(assigned: AssignedFolders) -> ScanSnapshot {
+        ScanSnapshot {
+            options: ScanOptions::default(),
+            uninstall: vec![UninstallEntry {
+                id: "viewer".to_string(),
+                name: "Contoso Viewer".to_string(),
+                publisher: None,
+                estimated_bytes: 0,
+                install_location: None,
+                display_icon: None,
+                uninstall_string: None,
+                registry_keys: Vec::new(),
+            }],
+            roots: Roots {
+                local: None,
+                roaming: None,
+                local_low: None,
+                program_data: None,
+            },
+            assigned: RwLock::new(assigned),
+            size_cache: SizeCache::default(),
+            history: Vec::new(),
+            records: RwLock::default(),
+        }
+    }
+
+    #[test]
+    fn parent_components_cannot_escape_the_entry() {
+        let roots = [PathBuf::from("/data/Contoso")];
+        assert!(is_within(Path::new("/data/Contoso"), &roots));
+        assert!(is_within(Path::new("/data/Contoso/cache/x"), &roots));
+        assert!(!is_within(Path::new("/data/Contoso/../../etc"), &roots));
+        assert!(!is_within(Path::new("/data/Contoso/a/../b"), &roots));
+        assert!(!is_within(Path::new("/data/ContosoOther"), &roots));
+    }
+
+    #[test]
+    fn breakdown_paths_follow_each_kind() {
+        let mut assigned = AssignedFolders::default();
+        assigned
+            .local
+            .insert("viewer".into(), vec![PathBuf::from("/local/Contoso")]);
+        assigned
+            .extra
+            .insert("viewer".into(), vec![PathBuf::from("/games/Contoso")]);
+        assigned.shared.push(SharedFolder {
+            path: PathBuf::from("/roaming/Shared"),
+            owners: vec![SharedOwner {
+                app_id: "viewer".into(),
+                app_name: "Contoso Viewer".into(),
+                score: 10,
+            }],
+        });
+        let snapshot = snapshot(assigned);
+
+        let paths = |kind: &str| breakdown_paths(&snapshot, "viewer", kind).unwrap();
+        assert_eq!(paths("appDataLocal"), [PathBuf::from("/local/Contoso")]);
+        assert_eq!(paths("extraData"), [PathBuf::from("/games/Contoso")]);
+        assert_eq!(paths("shared"), [PathBuf::from("/roaming/Shared")]);
+        assert!(paths("program").is_empty());
+        assert!(paths("appDataRoaming").is_empty());
+        for kind in BREAKDOWN_KINDS {
+            assert!(breakdown_paths(&snapshot, "viewer", kind).is_ok(), "{kind}");
+        }
+        assert!(breakdown_paths(&snapshot, "viewer", "bogus").is_err());
+        assert!(breakdown_paths(&snapshot, "missing", "shared").is_err());
+    }
+}
@@ -0,0 +1,604 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::Hash;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use jwalk::{Parallelism, WalkDir};
+use xxhash_rust::xxh3::{xxh3_64, Xxh3};
+
+use super::drill_down::{breakdown_paths, BREAKDOWN_KINDS};
+use super::size_index::mtime_nanos;
+use super::storage::{file_path, read_json_at, write_json_at};
+use super::ScanSnapshot;
+use crate::apps::disk_usage::file_usage;
+use crate::apps::{
+    CancelToken, DuplicateFile, DuplicateOptions, DuplicateReport, DuplicateSet, ScanProgress,
+};
+
+const HASH_CACHE_FILE: &str = "duplicate_hashes.json";
+const HASH_CACHE_VERSION: u32 = 1;
+
+/// 第二轮只读取文件开头这么多字节，大多数大小相同但内容不同的文件在此即可区分。
+const PARTIAL_HASH_BYTES: usize = 64 * 1024;
+
+const READ_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// 每算完这么多个完整哈希保存一次，进程意外退出时也只损失少量进度。
+const SAVE_EVERY: u32 = 200;
+
+/// 参与比较的文件及其归属软件 id。
+struct Candidate {
+    path: PathBuf,
+    len: u64,
+    mtime: u64,
+    owners: Vec<String>,
+}
+
+/// 按路径缓存的哈希；大小与 mtime 不变时直接复用。
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CachedHash {
+    len: u64,
+    mtime: u64,
+    #[serde(default)]
+    partial: Option<u64>,
+    /// xxh3-128 的十六进制表示。
+    #[serde(default)]
+    full: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HashCacheFile {
+    version: u32,
+    files: HashMap<String, CachedHash>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HashCacheFileRef<'a> {
+    version: u32,
+    files: &'a HashMap<String, CachedHash>,
+}
+
+#[derive(Default)]
+struct HashCache {
+    /// 保存位置；无法定位本地数据目录时只在内存中缓存。
+    path: Option<PathBuf>,
+    files: HashMap<String, CachedHash>,
+    reused: u32,
+    unsaved: u32,
+}
+
+impl HashCache {
+    fn load() -> HashCache {
+        match file_path(HASH_CACHE_FILE) {
+            Some(path) => HashCache::load_from(&path),
+            None => HashCache::default(),
+        }
+    }
+
+    fn load_from(path: &Path) -> HashCache {
+        let files = read_json_at::<HashCacheFile>(path)
+            .filter(|f| f.version == HASH_CACHE_VERSION)
+            .map(|f| f.files)
+            .unwrap_or_default();
+        HashCache {
+            path: Some(path.to_path_buf()),
+            files,
+            ..HashCache::default()
+        }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        self.unsaved = 0;
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| "无法定位本地数据目录".to_string())?;
+        write_json_at(
+            path,
+            &HashCacheFileRef {
+                version: HASH_CACHE_VERSION,
+                files: &self.files,
+            },
+        )
+    }
+
+    /// 文件变化后旧的哈希作废。
+    fn entry(&mut self, file: &Candidate) -> &mut CachedHash {
+        let fresh = CachedHash {
+            len: file.len,
+            mtime: file.mtime,
+            partial: None,
+            full: None,
+        };
+        let entry = self
+            .files
+            .entry(file.path.to_string_lossy().to_string())
+            .or_insert_with(|| fresh.clone());
+        if entry.len != file.len || entry.mtime != file.mtime {
+            *entry = fresh;
+        }
+        entry
+    }
+
+    fn partial(&mut self, file: &Candidate) -> Option<u64> {
+        let entry = self.entry(file);
+        if entry.partial.is_none() {
+            entry.partial = partial_hash(file);
+        }
+        entry.partial
+    }
+
+    fn full(&mut self, file: &Candidate, cancel: &CancelToken) -> Option<String> {
+        let entry = self.entry(file);
+        if let Some(h) = &entry.full {
+            let h = h.clone();
+            self.reused = self.reused.saturating_add(1);
+            return Some(h);
+        }
+        let h = full_hash(file, cancel)?;
+        entry.full = Some(h.clone());
+        self.unsaved = self.unsaved.saturating_add(1);
+        Some(h)
+    }
+}
+
+pub(super) fn find_duplicates(
+    snapshot: &ScanSnapshot,
+    options: &DuplicateOptions,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(ScanProgress),
+) -> Option<DuplicateReport> {
+    let files = collect_candidates(snapshot, options, cancel, &mut on_progress)?;
+    let files_scanned = files.len() as u32;
+    let seen: HashSet<String> = files
+        .iter()
+        .map(|f| f.path.to_string_lossy().to_string())
+        .collect();
+
+    let mut cache = HashCache::load();
+    let groups = group_duplicates(files, &mut cache, cancel, &mut on_progress)?;
+
+    // 完整结束后丢弃已不在归因目录中的文件。
+    cache.files.retain(|path, _| seen.contains(path));
+    let _ = cache.save();
+
+    let report = build_report(snapshot, groups, options, files_scanned, cache.reused);
+    on_progress(ScanProgress {
+        phase: "done".into(),
+        current: report.set_count,
+        total: report.set_count,
+        message: "查找完成".into(),
+        walk: None,
+        phase_timings: Vec::new(),
+    });
+    Some(report)
+}
+
+/// 依次按大小、开头 64 KiB 的哈希、完整内容的 xxh3-128 分组，返回内容相同的文件组。
+/// 取消时保存已算出的哈希后返回 `None`。
+fn group_duplicates(
+    files: Vec<Candidate>,
+    cache: &mut HashCache,
+    cancel: &CancelToken,
+    on_progress: &mut impl FnMut(ScanProgress),
+) -> Option<Vec<Vec<Candidate>>> {
+    let groups = regroup(vec![files], |f| Some(f.len));
+
+    let groups = regroup_with_progress(
+        groups,
+        "partial",
+        "正在比较文件开头…",
+        cancel,
+        on_progress,
+        |f| cache.partial(f),
+    );
+    let Some(groups) = groups else {
+        let _ = cache.save();
+        return None;
+    };
+
+    let groups = regroup_with_progress(
+        groups,
+        "full",
+        "正在比较完整内容…",
+        cancel,
+        on_progress,
+        |f| {
+            let h = cache.full(f, cancel);
+            if cache.unsaved >= SAVE_EVERY {
+                let _ = cache.save();
+            }
+            h
+        },
+    );
+    if groups.is_none() {
+        // 取消时保留全部记录，下次查找从这里继续。
+        let _ = cache.save();
+    }
+    groups
+}
+
+/// 遍历所有软件的明细目录；同一目录只遍历一次，同一文件的归属软件合并，
+/// 硬链接指向同一份数据，只保留第一个路径。
+fn collect_candidates(
+    snapshot: &ScanSnapshot,
+    options: &DuplicateOptions,
+    cancel: &CancelToken,
+    on_progress: &mut impl FnMut(ScanProgress),
+) -> Option<Vec<Candidate>> {
+    let mut roots: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    for u in &snapshot.uninstall {
+        for kind in BREAKDOWN_KINDS {
+            for p in breakdown_paths(snapshot, &u.id, kind).unwrap_or_default() {
+                let owners = roots.entry(p).or_default();
+                if !owners.contains(&u.id) {
+                    owners.push(u.id.clone());
+                }
+            }
+        }
+    }
+    let mut roots: Vec<(PathBuf, Vec<String>)> = roots.into_iter().collect();
+    roots.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let total = roots.len() as u32;
+    let mut files: HashMap<PathBuf, Candidate> = HashMap::new();
+    let mut link_paths: HashMap<(u64, u64), PathBuf> = HashMap::new();
+    for (i, (root, owners)) in roots.into_iter().enumerate() {
+        on_progress(ScanProgress {
+            phase: "collect".into(),
+            current: i as u32,
+            total,
+            message: "正在收集文件…".into(),
+            walk: None,
+            phase_timings: Vec::new(),
+        });
+        let walk = WalkDir::new(&root)
+            .skip_hidden(false)
+            .follow_links(false)
+            .parallelism(Parallelism::Serial);
+        for e in walk.into_iter().flatten() {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            if !e.file_type().is_file() {
+                continue;
+            }
+            let Ok(meta) = e.metadata() else {
+                continue;
+            };
+            if meta.len() < options.min_file_bytes {
+                continue;
+            }
+            let mut path = e.path();
+            if let Some(id) = file_usage(&path, &meta).link_id {
+                path = link_paths.entry(id).or_insert(path).clone();
+            }
+            let file = files.entry(path.clone()).or_insert_with(|| Candidate {
+                path,
+                len: meta.len(),
+                mtime: mtime_nanos(&meta),
+                owners: Vec::new(),
+            });
+            for o in &owners {
+                if !file.owners.contains(o) {
+                    file.owners.push(o.clone());
+                }
+            }
+        }
+    }
+    Some(files.into_values().collect())
+}
+
+/// 按 `key` 把每组再细分，只保留至少两个文件的组；取不到 key（读取失败）的文件被丢弃。
+fn regroup<K: Hash + Eq>(
+    groups: Vec<Vec<Candidate>>,
+    mut key: impl FnMut(&Candidate) -> Option<K>,
+) -> Vec<Vec<Candidate>> {
+    let mut out = Vec::new();
+    for group in groups {
+        let mut split: HashMap<K, Vec<Candidate>> = HashMap::new();
+        for f in group {
+            if let Some(k) = key(&f) {
+                split.entry(k).or_default().push(f);
+            }
+        }
+        out.extend(split.into_values().filter(|g| g.len() > 1));
+    }
+    out
+}
+
+/// 与 [`regroup`] 相同，但逐个文件汇报进度；取消时返回 `None`。
+fn regroup_with_progress<K: Hash + Eq>(
+    groups: Vec<Vec<Candidate>>,
+    phase: &str,
+    message: &str,
+    cancel: &CancelToken,
+    on_progress: &mut impl FnMut(ScanProgress),
+    mut key: impl FnMut(&Candidate) -> Option<K>,
+) -> Option<Vec<Vec<Candidate>>> {
+    let total = groups.iter().map(Vec::len).sum::<usize>() as u32;
+    let mut current = 0u32;
+    let out = regroup(groups, |f| {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        current = current.saturating_add(1);
+        on_progress(ScanProgress {
+            phase: phase.into(),
+            current,
+            total,
+            message: message.into(),
+            walk: None,
+            phase_timings: Vec::new(),
+        });
+        key(f)
+    });
+    (!cancel.is_cancelled()).then_some(out)
+}
+
+fn partial_hash(file: &Candidate) -> Option<u64> {
+    let mut buf = Vec::with_capacity(PARTIAL_HASH_BYTES);
+    File::open(&file.path)
+        .ok()?
+        .take(PARTIAL_HASH_BYTES as u64)
+        .read_to_end(&mut buf)
+        .ok()?;
+    Some(xxh3_64(&buf))
+}
+
+/// 大文件读取途中也检查取消。
+fn full_hash(file: &Candidate, cancel: &CancelToken) -> Option<String> {
+    let mut f = File::open(&file.path).ok()?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0u8; READ_BUFFER_BYTES];
+    loop {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        let n = f.read(&mut buf).ok()?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Some(format!("{:032x}", hasher.digest128()))
+}
+
+fn build_report(
+    snapshot: &ScanSnapshot,
+    groups: Vec<Vec<Candidate>>,
+    options: &DuplicateOptions,
+    files_scanned: u32,
+    reused_hashes: u32,
+) -> DuplicateReport {
+    let names: HashMap<&str, &str> = snapshot
+        .uninstall
+        .iter()
+        .map(|u| (u.id.as_str(), u.name.as_str()))
+        .collect();
+    let app_names = |owners: &[String]| -> Vec<String> {
+        owners
+            .iter()
+            .map(|id| names.get(id.as_str()).copied().unwrap_or(id).to_string())
+            .collect()
+    };
+
+    let mut sets: Vec<DuplicateSet> = groups
+        .into_iter()
+        .map(|mut group| {
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+            let file_bytes = group[0].len;
+            let mut apps: Vec<String> = Vec::new();
+            let files = group
+                .iter()
+                .map(|f| {
+                    let owners = app_names(&f.owners);
+                    for name in &owners {
+                        if !apps.contains(name) {
+                            apps.push(name.clone());
+                        }
+                    }
+                    DuplicateFile {
+                        path: f.path.to_string_lossy().to_string(),
+                        apps: owners,
+                    }
+                })
+                .collect();
+            DuplicateSet {
+                file_bytes,
+                wasted_bytes: file_bytes.saturating_mul(group.len() as u64 - 1),
+                files,
+                apps,
+            }
+        })
+        .collect();
+    sets.sort_by_key(|s| Reverse(s.wasted_bytes));
+
+    let set_count = sets.len() as u32;
+    let wasted_bytes = sets.iter().map(|s| s.wasted_bytes).sum();
+    sets.truncate(options.limit as usize);
+    DuplicateReport {
+        sets,
+        set_count,
+        wasted_bytes,
+        files_scanned,
+        reused_hashes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::windows::matching::AssignedFolders;
+    use crate::apps::windows::roots::Roots;
+    use crate::apps::windows::sizing::SizeCache;
+    use crate::apps::windows::uninstall::UninstallEntry;
+    use crate::apps::ScanOptions;
+    use std::sync::RwLock;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "appmanager-duplicates-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn candidate(path: PathBuf) -> Candidate {
+        let meta = std::fs::metadata(&path).unwrap();
+        Candidate {
+            path,
+            len: meta.len(),
+            mtime: mtime_nanos(&meta),
+            owners: vec!["viewer".to_string()],
+        }
+    }
+
+    fn write(dir: &Path, name: &str, content: &[u8]) -> Candidate {
+        std::fs::write(dir.join(name), content).unwrap();
+        candidate(dir.join(name))
+    }
+
+    fn snapshot() -> ScanSnapshot {
+        ScanSnapshot {
+            options: ScanOptions::default(),
+            uninstall: vec![UninstallEntry {
+                id: "viewer".to_string(),
+                name: "Contoso Viewer".to_string(),
+                publisher: None,
+                estimated_bytes: 0,
+                install_location: None,
+                display_icon: None,
+                uninstall_string: None,
+                registry_keys: Vec::new(),
+            }],
+            roots: Roots {
+                local: None,
+                roaming: None,
+                local_low: None,
+                program_data: None,
+            },
+            assigned: RwLock::new(AssignedFolders::default()),
+            size_cache: SizeCache::default(),
+            history: Vec::new(),
+            records: RwLock::default(),
+        }
+    }
+
+    fn group(files: Vec<Candidate>, cache: &mut HashCache) -> Vec<Vec<Candidate>> {
+        group_duplicates(files, cache, &CancelToken::default(), &mut |_| {}).unwrap()
+    }
+
+    #[test]
+    fn only_identical_content_forms_a_duplicate_set() {
+        let dir = temp_dir("group");
+        let head = vec![7u8; PARTIAL_HASH_BYTES];
+        let tail = |b: u8| [head.as_slice(), &[b; 100]].concat();
+        let files = vec![
+            // 大小相同，开头就不同。
+            write(&dir, "a.bin", &[1u8; 5000]),
+            write(&dir, "b.bin", &[2u8; 5000]),
+            // 开头 64 KiB 相同，只有完整哈希能区分。
+            write(&dir, "c.bin", &tail(3)),
+            write(&dir, "d.bin", &tail(4)),
+            write(&dir, "e.bin", &[5u8; 3000]),
+            write(&dir, "f.bin", &[5u8; 3000]),
+            write(&dir, "g.bin", &[5u8; 3000]),
+        ];
+        let mut cache = HashCache::load_from(&dir.join("hashes.json"));
+        let groups = group(files, &mut cache);
+
+        let report = build_report(&snapshot(), groups, &DuplicateOptions::default(), 7, 0);
+        assert_eq!(report.set_count, 1);
+        let set = &report.sets[0];
+        let names: Vec<_> = set
+            .files
+            .iter()
+            .map(|f| Path::new(&f.path).file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["e.bin", "f.bin", "g.bin"]);
+        assert_eq!(set.file_bytes, 3000);
+        assert_eq!(set.wasted_bytes, 6000);
+        assert_eq!(report.wasted_bytes, 6000);
+        assert_eq!(set.apps, ["Contoso Viewer"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn full_hashes_are_reused_after_reload() {
+        let dir = temp_dir("reuse");
+        let cache_file = dir.join("hashes.json");
+        let first = vec![
+            write(&dir, "a.bin", &[9u8; 4000]),
+            write(&dir, "b.bin", &[9u8; 4000]),
+        ];
+        let mut cache = HashCache::load_from(&cache_file);
+        assert_eq!(group(first, &mut cache).len(), 1);
+        assert_eq!(cache.reused, 0);
+        cache.save().unwrap();
+
+        let second = vec![candidate(dir.join("a.bin")), candidate(dir.join("b.bin"))];
+        let mut cache = HashCache::load_from(&cache_file);
+        assert_eq!(group(second, &mut cache).len(), 1);
+        assert_eq!(cache.reused, 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cached_hashes_are_dropped_when_size_or_mtime_change() {
+        let dir = temp_dir("stale");
+        let file = write(&dir, "a.bin", &[1u8; 100]);
+        let mut cache = HashCache::default();
+        assert!(cache.partial(&file).is_some());
+        assert!(cache.full(&file, &CancelToken::default()).is_some());
+
+        let touched = Candidate {
+            mtime: file.mtime + 1,
+            ..candidate(file.path.clone())
+        };
+        assert!(cache.entry(&touched).full.is_none());
+        assert!(cache.full(&file, &CancelToken::default()).is_some());
+        let resized = write(&dir, "a.bin", &[1u8; 200]);
+        let entry = cache.entry(&resized);
+        assert!(entry.partial.is_none() && entry.full.is_none());
+        assert_eq!(entry.len, 200);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cancelled_search_saves_hashes_already_computed() {
+        let dir = temp_dir("cancel");
+        let cache_file = dir.join("hashes.json");
+        let files = vec![
+            write(&dir, "a.bin", &[3u8; 2000]),
+            write(&dir, "b.bin", &[3u8; 2000]),
+            write(&dir, "c.bin", &[3u8; 2000]),
+        ];
+        let cancel = CancelToken::default();
+        let mut full_started = 0;
+        let mut cache = HashCache::load_from(&cache_file);
+        // 第二个文件开始计算完整哈希时取消：第一个文件的结果应当已经保存。
+        let groups = group_duplicates(files, &mut cache, &cancel, &mut |p| {
+            if p.phase == "full" {
+                full_started += 1;
+                if full_started == 2 {
+                    cancel.cancel();
+                }
+            }
+        });
+        assert!(groups.is_none());
+
+        let reloaded = HashCache::load_from(&cache_file);
+        let hashes = reloaded.files.values();
+        assert_eq!(hashes.clone().filter(|h| h.partial.is_some()).count(), 3);
+        assert_eq!(hashes.filter(|h| h.full.is_some()).count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::corrections::Correction;
+use super::known_apps::match_known_apps_in;
+use super::matching::{assign_folders, build_app_tokens, AssignedFolders};
+use super::roots::{RootFolders, Roots};
+use super::uninstall::UninstallEntry;
+use super::version_info::FolderEvidence;
+use crate::apps::pe::VersionInfo;
+use crate::apps::{MatchEvalDataset, MatchEvalReport, MatchMisattribution, MatchParams};
+
+/// 用标注数据集离线跑一遍完整的归因：签名库、版本资源证据、用户修正与 token 匹配，
+/// 不访问注册表与文件系统。
+pub(super) fn evaluate_matching(
+    dataset: &MatchEvalDataset,
+    params: &MatchParams,
+) -> MatchEvalReport {
+    let uninstall: Vec<UninstallEntry> = dataset
+        .apps
+        .iter()
+        .map(|a| UninstallEntry {
+            id: a.id.clone(),
+            name: a.name.clone(),
+            publisher: a.publisher.clone(),
+            estimated_bytes: 0,
+            install_location: None,
+            display_icon: None,
+            uninstall_string: None,
+            registry_keys: Vec::new(),
+        })
+        .collect();
+
+    let roots = Roots {
+        local: build_root("appDataLocal", dataset),
+        roaming: build_root("appDataRoaming", dataset),
+        local_low: build_root("appDataLocalLow", dataset),
+        program_data: build_root("programData", dataset),
+    };
+
+    // 数据集只列出顶层文件夹，签名库中更深的路径在其顶层文件夹存在时视为存在。
+    let listed: HashSet<PathBuf> = dataset
+        .folders
+        .iter()
+        .map(|f| folder_path(&f.root, &f.folder))
+        .collect();
+    let known = match_known_apps_in(&uninstall, &roots, |p| {
+        top_folder(p).is_some_and(|top| listed.contains(&top))
+    });
+
+    let evidence = FolderEvidence {
+        local: evidence_for("appDataLocal", dataset),
+        roaming: evidence_for("appDataRoaming", dataset),
+        local_low: evidence_for("appDataLocalLow", dataset),
+        program_data: evidence_for("programData", dataset),
+    };
+
+    let tokens = build_app_tokens(&uninstall);
+    let corrections: Vec<Correction> = dataset
+        .corrections
+        .iter()
+        .filter_map(|c| {
+            let app = tokens.iter().find(|t| t.app_id == c.app)?;
+            Some(Correction::for_app(&c.root, &c.folder, app.clone(), 0))
+        })
+        .collect();
+
+    let assigned = assign_folders(&roots, &tokens, &known, &evidence, &corrections, params);
+    let predicted_by_path = predicted_owners(&assigned);
+
+    let mut expected_count = 0u32;
+    let mut predicted_count = 0u32;
+    let mut correct_count = 0u32;
+    let mut misattributions = Vec::new();
+
+    for f in &dataset.folders {
+        let predicted = predicted_by_path
+            .get(&folder_path(&f.root, &f.folder))
+            .cloned();
+        if f.expected.is_some() {
+            expected_count += 1;
+        }
+        if predicted.is_some() {
+            predicted_count += 1;
+        }
+        if f.expected.is_some() && predicted == f.expected {
+            correct_count += 1;
+        } else if predicted != f.expected {
+            misattributions.push(MatchMisattribution {
+                root: f.root.clone(),
+                folder: f.folder.clone(),
+                expected: f.expected.clone(),
+                predicted,
+            });
+        }
+    }
+
+    MatchEvalReport {
+        folder_count: dataset.folders.len() as u32,
+        expected_count,
+        predicted_count,
+        correct_count,
+        precision: ratio(correct_count, predicted_count),
+        recall: ratio(correct_count, expected_count),
+        misattributions,
+    }
+}
+
+fn build_root(kind: &str, dataset: &MatchEvalDataset) -> Option<RootFolders> {
+    let folders: HashMap<String, PathBuf> = dataset
+        .folders
+        .iter()
+        .filter(|f| f.root == kind)
+        .map(|f| (f.folder.to_lowercase(), folder_path(kind, &f.folder)))
+        .collect();
+    if folders.is_empty() {
+        return None;
+    }
+    Some(RootFolders {
+        path: PathBuf::from(kind),
+        folders,
+    })
+}
+
+fn evidence_for(kind: &str, dataset: &MatchEvalDataset) -> HashMap<String, Vec<VersionInfo>> {
+    dataset
+        .folders
+        .iter()
+        .filter(|f| f.root == kind && !f.evidence.is_empty())
+        .map(|f| {
+            let infos = f
+                .evidence
+                .iter()
+                .map(|e| VersionInfo {
+                    company_name: e.company_name.clone(),
+                    product_name: e.product_name.clone(),
+                    file_description: e.file_description.clone(),
+                    product_version: e.product_version.clone(),
+                })
+                .collect();
+            (f.folder.to_lowercase(), infos)
+        })
+        .collect()
+}
+
+fn folder_path(kind: &str, folder: &str) -> PathBuf {
+    PathBuf::from(kind).join(folder.to_lowercase())
+}
+
+/// 路径所在的「根目录类型/顶层文件夹」，与 [`folder_path`] 的形式相同。
+fn top_folder(path: &Path) -> Option<PathBuf> {
+    let mut components = path.components();
+    let kind = components.next()?.as_os_str().to_string_lossy().to_string();
+    let folder = components.next()?.as_os_str().to_string_lossy().to_string();
+    Some(folder_path(&kind, &folder))
+}
+
+/// 共享文件夹以得分最高的候选作为预测结果。签名库认领的深层路径算作其顶层文件夹的归属，
+/// 顶层文件夹本身有归属时以后者为准；多个深层归属时取 app id 最小的，结果与遍历顺序无关。
+fn predicted_owners(assigned: &AssignedFolders) -> HashMap<PathBuf, String> {
+    let mut candidates: Vec<(PathBuf, bool, &String)> = Vec::new();
+    for map in [
+        &assigned.local,
+        &assigned.roaming,
+        &assigned.local_low,
+        &assigned.program_data,
+    ] {
+        for (app_id, paths) in map {
+            for p in paths {
+                if let Some(top) = top_folder(p) {
+                    let deep = p.components().count() > 2;
+                    candidates.push((top, deep, app_id));
+                }
+            }
+        }
+    }
+    for folder in &assigned.shared {
+        if let (Some(top), Some(owner)) = (top_folder(&folder.path), folder.owners.first()) {
+            candidates.push((top, false, &owner.app_id));
+        }
+    }
+    candidates.sort();
+
+    let mut out = HashMap::new();
+    for (top, _, app_id) in candidates {
+        out.entry(top).or_insert_with(|| app_id.clone());
+    }
+    out
+}
+
+fn ratio(n: u32, d: u32) -> f64 {
+    if d == 0 {
+        0.0
+    } else {
+        n as f64 / d as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::{MatchEvalApp, MatchEvalCorrection, MatchEvalEvidence, MatchEvalFolder};
+
+    fn app(id: &str, name: &str, publisher: &str) -> MatchEvalApp {
+        MatchEvalApp {
+            id: id.to_string(),
+            name: name.to_string(),
+            publisher: Some(publisher.to_string()),
+        }
+    }
+
+    fn folder(root: &str, name: &str, expected: Option<&str>) -> MatchEvalFolder {
+        MatchEvalFolder {
+            root: root.to_string(),
+            folder: name.to_string(),
+            expected: expected.map(str::to_string),
+            evidence: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn uses_known_apps_evidence_and_corrections() {
+        let mut evidence_folder = folder("appDataRoaming", "X7Data", Some("widget"));
+        evidence_folder.evidence.push(MatchEvalEvidence {
+            company_name: Some("Acme Corporation".to_string()),
+            product_name: Some("Acme Widget Studio".to_string()),
+            ..Default::default()
+        });
+        let dataset = MatchEvalDataset {
+            apps: vec![
+                app("chrome", "Google Chrome", "Google LLC"),
+                app("widget", "Acme Widget Studio", "Acme Corporation"),
+                app("notes", "Quillpad Notes", "Quillpad"),
+            ],
+            folders: vec![
+                folder("appDataLocal", "Google", Some("chrome")),
+                evidence_folder,
+                folder("appDataRoaming", "SharedStuff", Some("notes")),
+                folder("appDataRoaming", "Unrelated", None),
+            ],
+            corrections: vec![MatchEvalCorrection {
+                root: "appDataRoaming".to_string(),
+                folder: "SharedStuff".to_string(),
+                app: "notes".to_string(),
+            }],
+        };
+
+        let report = evaluate_matching(&dataset, &MatchParams::default());
+        assert!(report.misattributions.is_empty());
+        assert_eq!(report.correct_count, 3);
+        assert_eq!(report.precision, 1.0);
+        assert_eq!(report.recall, 1.0);
+    }
+
+    #[test]
+    fn name_threshold_controls_token_matches() {
+        let dataset = MatchEvalDataset {
+            apps: vec![app("notes", "Quillpad Notes", "Quillpad")],
+            folders: vec![folder("appDataRoaming", "Quillpad", Some("notes"))],
+            corrections: Vec::new(),
+        };
+
+        let report = evaluate_matching(&dataset, &MatchParams::default());
+        assert_eq!(report.correct_count, 1);
+
+        let strict = MatchParams {
+            name_threshold: 10_000,
+            ..MatchParams::default()
+        };
+        let report = evaluate_matching(&dataset, &strict);
+        assert_eq!(report.predicted_count, 0);
+        assert_eq!(report.recall, 0.0);
+    }
+}
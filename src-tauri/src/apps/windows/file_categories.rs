@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use crate::apps::CategoryBytes;
+
+/// 应用目录内文件的用途分类，用于区分可清理的缓存与用户数据。
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(super) enum FileCategory {
+    Cache,
+    Logs,
+    CrashDumps,
+    Temp,
+    Updates,
+    Databases,
+    Media,
+    Binaries,
+    Other,
+}
+
+const CATEGORY_COUNT: usize = 9;
+
+const ALL_CATEGORIES: [FileCategory; CATEGORY_COUNT] = [
+    FileCategory::Cache,
+    FileCategory::Logs,
+    FileCategory::CrashDumps,
+    FileCategory::Temp,
+    FileCategory::Updates,
+    FileCategory::Databases,
+    FileCategory::Media,
+    FileCategory::Binaries,
+    FileCategory::Other,
+];
+
+impl FileCategory {
+    fn kind(self) -> &'static str {
+        match self {
+            FileCategory::Cache => "cache",
+            FileCategory::Logs => "logs",
+            FileCategory::CrashDumps => "crashDumps",
+            FileCategory::Temp => "temp",
+            FileCategory::Updates => "updates",
+            FileCategory::Databases => "databases",
+            FileCategory::Media => "media",
+            FileCategory::Binaries => "binaries",
+            FileCategory::Other => "other",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            FileCategory::Cache => "缓存",
+            FileCategory::Logs => "日志",
+            FileCategory::CrashDumps => "崩溃转储",
+            FileCategory::Temp => "临时文件",
+            FileCategory::Updates => "更新与安装包",
+            FileCategory::Databases => "数据库",
+            FileCategory::Media => "媒体文件",
+            FileCategory::Binaries => "程序与库",
+            FileCategory::Other => "其他",
+        }
+    }
+}
+
+/// 按类别累计的逻辑大小。
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct CategorySizes([u64; CATEGORY_COUNT]);
+
+impl CategorySizes {
+    pub(super) fn add(&mut self, category: FileCategory, bytes: u64) {
+        let slot = &mut self.0[category as usize];
+        *slot = slot.saturating_add(bytes);
+    }
+
+    pub(super) fn merge(self, other: CategorySizes) -> CategorySizes {
+        let mut out = self;
+        for (a, b) in out.0.iter_mut().zip(other.0) {
+            *a = a.saturating_add(b);
+        }
+        out
+    }
+
+    /// 整个子树归入同一类别，例如 `Cache` 目录下的所有文件都是缓存。
+    pub(super) fn collapse_into(self, category: FileCategory) -> CategorySizes {
+        let mut out = CategorySizes::default();
+        out.add(
+            category,
+            self.0.iter().fold(0u64, |a, b| a.saturating_add(*b)),
+        );
+        out
+    }
+
+    pub(super) fn map(self, f: impl Fn(u64) -> u64) -> CategorySizes {
+        CategorySizes(self.0.map(f))
+    }
+
+    /// 非零类别，按大小降序。
+    pub(super) fn entries(&self) -> Vec<CategoryBytes> {
+        let mut out: Vec<CategoryBytes> = ALL_CATEGORIES
+            .iter()
+            .filter(|c| self.0[**c as usize] > 0)
+            .map(|c| CategoryBytes {
+                kind: c.kind().into(),
+                label: c.label().into(),
+                bytes: self.0[*c as usize],
+            })
+            .collect();
+        out.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+        out
+    }
+}
+
+/// 目录名本身即表明用途时，其下所有文件都归入该类别。只在并入上层目录时归类，
+/// 被归因的文件夹本身（如名为 `Cache` 的软件数据目录）仍按文件细分。
+pub(super) fn category_of_dir(dir: &Path) -> Option<FileCategory> {
+    let name = dir.file_name()?.to_string_lossy().to_lowercase();
+    let category = match name.as_str() {
+        "log" | "logs" | "logfiles" => FileCategory::Logs,
+        "crashpad" | "crashdumps" | "crash reports" | "crashes" | "minidumps" | "dumps" => {
+            FileCategory::CrashDumps
+        }
+        "temp" | "tmp" | "temporary" => FileCategory::Temp,
+        // 不含 "packages"：LOCALAPPDATA\Packages 是 UWP 应用的数据目录。
+        "update" | "updates" | "updater" | "installer" | "installers" | "package cache" => {
+            FileCategory::Updates
+        }
+        "indexeddb" | "databases" | "local storage" | "session storage" | "leveldb" => {
+            FileCategory::Databases
+        }
+        "cache" | "caches" | "cache2" | "cachestorage" | "cache_data" | "cacheddata"
+        | "gpucache" | "shadercache" | "grshadercache" | "dawncache" | "graphitedawncache"
+        | "inetcache" | "webcache" => FileCategory::Cache,
+        // Code Cache、Media Cache、js_cache、.cache 等以分隔符加 cache 结尾的名称。
+        _ if CACHE_SUFFIXES.iter().any(|s| name.ends_with(s)) => FileCategory::Cache,
+        _ => return None,
+    };
+    Some(category)
+}
+
+const CACHE_SUFFIXES: [&str; 4] = [" cache", "_cache", "-cache", ".cache"];
+
+pub(super) fn category_of_file(file: &Path) -> FileCategory {
+    let Some(ext) = file.extension() else {
+        return FileCategory::Other;
+    };
+    match ext.to_string_lossy().to_lowercase().as_str() {
+        "log" | "etl" | "evtx" => FileCategory::Logs,
+        "dmp" | "mdmp" | "hdmp" => FileCategory::CrashDumps,
+        "tmp" | "temp" | "partial" | "crdownload" => FileCategory::Temp,
+        "msi" | "msp" | "msu" | "cab" | "nupkg" | "msix" | "msixbundle" | "appx" | "appxbundle" => {
+            FileCategory::Updates
+        }
+        "db" | "db-wal" | "db-shm" | "sqlite" | "sqlite3" | "sqlite-wal" | "ldb" | "sst"
+        | "mdb" | "edb" | "accdb" | "realm" => FileCategory::Databases,
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "heic" | "mp4" | "mkv" | "mov"
+        | "avi" | "webm" | "wmv" | "mp3" | "wav" | "flac" | "ogg" | "m4a" | "aac" | "opus"
+        | "wma" => FileCategory::Media,
+        "exe" | "dll" | "sys" | "ocx" | "node" | "so" | "dylib" | "pyd" | "jar" | "winmd" => {
+            FileCategory::Binaries
+        }
+        _ => FileCategory::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> Option<FileCategory> {
+        category_of_dir(Path::new(name))
+    }
+
+    #[test]
+    fn cache_dirs_match_whole_names_or_suffixes() {
+        for name in [
+            "Cache",
+            "Code Cache",
+            "GPUCache",
+            "cache2",
+            "CacheStorage",
+            "js_cache",
+            ".cache",
+        ] {
+            assert_eq!(dir(name), Some(FileCategory::Cache), "{name}");
+        }
+        for name in ["CacheManager", "Cachet", "WinCacheTool", "cachedfiles"] {
+            assert_eq!(dir(name), None, "{name}");
+        }
+        assert_eq!(dir("Package Cache"), Some(FileCategory::Updates));
+    }
+
+    #[test]
+    fn other_dir_names_are_case_insensitive() {
+        assert_eq!(dir("Logs"), Some(FileCategory::Logs));
+        assert_eq!(dir("Crashpad"), Some(FileCategory::CrashDumps));
+        assert_eq!(dir("Local Storage"), Some(FileCategory::Databases));
+        assert_eq!(dir("Packages"), None);
+    }
+}
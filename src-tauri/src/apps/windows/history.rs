@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use super::matching::{normalize_key, AssignedFolders};
+use super::size_index::now_secs;
+use super::storage::{read_json, write_json};
+use super::uninstall::{strip_version_suffix, UninstallEntry};
+
+const APP_HISTORY_FILE: &str = "app_history.json";
+const APP_HISTORY_VERSION: u32 = 1;
+
+/// 以往扫描中出现过的软件及最近一次归属到它的 AppData/ProgramData 文件夹。
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct AppHistoryEntry {
+    pub(super) id: String,
+    pub(super) name: String,
+    #[serde(default)]
+    pub(super) publisher: Option<String>,
+    /// 去掉版本号后的规范名；升级后注册表项改名时仍能认出同一软件。
+    pub(super) app_key: String,
+    pub(super) last_seen: u64,
+    /// 首次发现它已不在卸载列表中的扫描时间；仍安装着时为空。
+    #[serde(default)]
+    pub(super) removed_at: Option<u64>,
+    pub(super) folders: Vec<PathBuf>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppHistoryFile {
+    version: u32,
+    apps: Vec<AppHistoryEntry>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppHistoryFileRef<'a> {
+    version: u32,
+    apps: &'a [AppHistoryEntry],
+}
+
+fn load_app_history() -> Vec<AppHistoryEntry> {
+    read_json::<AppHistoryFile>(APP_HISTORY_FILE)
+        .filter(|f| f.version == APP_HISTORY_VERSION)
+        .map(|f| f.apps)
+        .unwrap_or_default()
+}
+
+/// 用本次扫描的软件列表与归属结果更新历史并写盘，返回更新后的全部记录。
+pub(super) fn update_app_history(
+    uninstall: &[UninstallEntry],
+    assigned: &AssignedFolders,
+) -> Vec<AppHistoryEntry> {
+    let old = load_app_history();
+    // 读不到卸载列表多半是注册表访问失败，不能据此把所有软件都当作已卸载。
+    if uninstall.is_empty() {
+        return old;
+    }
+
+    let apps = merge_app_history(old, uninstall, assigned, now_secs());
+    // 写入失败只影响之后的残留数据识别，不影响本次结果。
+    let _ = write_json(
+        APP_HISTORY_FILE,
+        &AppHistoryFileRef {
+            version: APP_HISTORY_VERSION,
+            apps: &apps,
+        },
+    );
+    apps
+}
+
+/// 仍安装的软件记下本次归属到它的全部文件夹：独占的、与其他软件共有的，以及签名库给出的
+/// 根目录之外的数据目录。不再出现的软件标记为已卸载；其文件夹都已删除后从历史中移除。
+fn merge_app_history(
+    old: Vec<AppHistoryEntry>,
+    uninstall: &[UninstallEntry],
+    assigned: &AssignedFolders,
+    now: u64,
+) -> Vec<AppHistoryEntry> {
+    let mut apps: Vec<AppHistoryEntry> = uninstall
+        .iter()
+        .map(|u| AppHistoryEntry {
+            id: u.id.clone(),
+            name: u.name.clone(),
+            publisher: u.publisher.clone(),
+            app_key: app_key(&u.name),
+            last_seen: now,
+            removed_at: None,
+            folders: [
+                &assigned.local,
+                &assigned.roaming,
+                &assigned.local_low,
+                &assigned.program_data,
+                &assigned.extra,
+            ]
+            .into_iter()
+            .filter_map(|m| m.get(&u.id))
+            .flatten()
+            .chain(
+                assigned
+                    .shared
+                    .iter()
+                    .filter(|f| f.owners.iter().any(|o| o.app_id == u.id))
+                    .map(|f| &f.path),
+            )
+            .cloned()
+            .collect(),
+        })
+        .collect();
+
+    let present_ids: HashSet<&str> = apps.iter().map(|a| a.id.as_str()).collect();
+    let present_keys: HashSet<&str> = apps.iter().map(|a| a.app_key.as_str()).collect();
+    let mut removed: Vec<AppHistoryEntry> = old
+        .into_iter()
+        .filter(|e| {
+            !present_ids.contains(e.id.as_str()) && !present_keys.contains(e.app_key.as_str())
+        })
+        .filter_map(|mut e| {
+            e.removed_at.get_or_insert(now);
+            e.folders.retain(|p| p.is_dir());
+            (!e.folders.is_empty()).then_some(e)
+        })
+        .collect();
+    apps.append(&mut removed);
+    apps
+}
+
+fn app_key(name: &str) -> String {
+    normalize_key(&strip_version_suffix(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::windows::matching::{SharedFolder, SharedOwner};
+
+    fn entry(id: &str, name: &str) -> UninstallEntry {
+        UninstallEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            publisher: None,
+            estimated_bytes: 0,
+            install_location: None,
+            display_icon: None,
+            uninstall_string: None,
+            registry_keys: Vec::new(),
+        }
+    }
+
+    fn owner(app_id: &str) -> SharedOwner {
+        SharedOwner {
+            app_id: app_id.to_string(),
+            app_name: app_id.to_string(),
+            score: 10,
+        }
+    }
+
+    #[test]
+    fn records_exclusive_shared_and_extra_folders() {
+        let mut assigned = AssignedFolders::default();
+        assigned
+            .local
+            .insert("a".into(), vec![PathBuf::from("local/A")]);
+        assigned
+            .extra
+            .insert("a".into(), vec![PathBuf::from("D:/Games/A")]);
+        assigned.shared.push(SharedFolder {
+            path: PathBuf::from("roaming/Vendor"),
+            owners: vec![owner("b"), owner("a")],
+        });
+        assigned.shared.push(SharedFolder {
+            path: PathBuf::from("roaming/Other"),
+            owners: vec![owner("b"), owner("c")],
+        });
+
+        let apps = merge_app_history(Vec::new(), &[entry("a", "App A")], &assigned, 100);
+
+        assert_eq!(
+            apps[0].folders,
+            ["local/A", "D:/Games/A", "roaming/Vendor"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn removed_apps_keep_only_folders_that_still_exist() {
+        let dir = std::env::temp_dir().join(format!("appmanager-history-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let old = |id: &str, name: &str, folders: Vec<PathBuf>| AppHistoryEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            publisher: None,
+            app_key: app_key(name),
+            last_seen: 50,
+            removed_at: None,
+            folders,
+        };
+        let history = vec![
+            old("gone", "Gone App", vec![dir.clone(), dir.join("deleted")]),
+            old("empty", "Empty App", vec![dir.join("deleted")]),
+            // 升级后注册表项改名，按去掉版本号的规范名认出同一软件。
+            old("tool-1", "Tool 1.0", vec![dir.clone()]),
+        ];
+        let uninstall = [entry("tool-2", "Tool 2.0")];
+
+        let apps = merge_app_history(history, &uninstall, &AssignedFolders::default(), 100);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let ids: Vec<&str> = apps.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["tool-2", "gone"]);
+        assert_eq!(apps[1].removed_at, Some(100));
+        assert_eq!(apps[1].folders, [dir]);
+    }
+}
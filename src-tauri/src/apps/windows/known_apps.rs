@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::matching::{normalize_key, AssignedFolders};
+use super::roots::Roots;
+use super::uninstall::{strip_version_suffix, UninstallEntry};
+
+/// 内置的常见软件数据目录签名库，随程序一起发布。
+const KNOWN_APPS_JSON: &str = include_str!("../../../data/known_apps.json");
+const KNOWN_APPS_VERSION: u32 = 1;
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KnownAppsFile {
+    version: u32,
+    apps: Vec<KnownApp>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KnownApp {
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    name_prefixes: Vec<String>,
+    #[serde(default)]
+    publisher: Option<String>,
+    paths: Vec<KnownPath>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KnownPath {
+    root: String,
+    path: String,
+}
+
+/// 签名库随程序编译进来，解析失败是打包错误，不能静默退化为空表。
+fn parse_known_apps() -> KnownAppsFile {
+    let file: KnownAppsFile =
+        serde_json::from_str(KNOWN_APPS_JSON).expect("内置的 known_apps.json 格式错误");
+    assert_eq!(
+        file.version, KNOWN_APPS_VERSION,
+        "内置的 known_apps.json 版本不匹配"
+    );
+    file
+}
+
+fn known_apps() -> &'static [KnownApp] {
+    static CACHE: OnceLock<Vec<KnownApp>> = OnceLock::new();
+    CACHE.get_or_init(|| parse_known_apps().apps)
+}
+
+/// 在启发式匹配之前，先用签名库为已知软件认领数据目录。
+pub(super) fn match_known_apps(uninstall: &[UninstallEntry], roots: &Roots) -> AssignedFolders {
+    match_known_apps_in(uninstall, roots, |p| p.is_dir())
+}
+
+/// 同 [`match_known_apps`]，由 `exists` 判断签名路径是否存在，离线评估时据标注数据判断。
+pub(super) fn match_known_apps_in(
+    uninstall: &[UninstallEntry],
+    roots: &Roots,
+    exists: impl Fn(&Path) -> bool,
+) -> AssignedFolders {
+    let mut out = AssignedFolders::default();
+    let program_files = std::env::var_os("ProgramFiles").map(PathBuf::from);
+    let program_files_x86 = std::env::var_os("ProgramFiles(x86)").map(PathBuf::from);
+
+    for known in known_apps() {
+        let Some(u) = uninstall.iter().find(|u| matches_entry(known, u)) else {
+            continue;
+        };
+
+        for kp in &known.paths {
+            let (base, target) = match kp.root.as_str() {
+                "appDataLocal" => (roots.local.as_ref().map(|r| &r.path), &mut out.local),
+                "appDataRoaming" => (roots.roaming.as_ref().map(|r| &r.path), &mut out.roaming),
+                "appDataLocalLow" => (
+                    roots.local_low.as_ref().map(|r| &r.path),
+                    &mut out.local_low,
+                ),
+                "programData" => (
+                    roots.program_data.as_ref().map(|r| &r.path),
+                    &mut out.program_data,
+                ),
+                "programFiles" => (program_files.as_ref(), &mut out.extra),
+                "programFilesX86" => (program_files_x86.as_ref(), &mut out.extra),
+                _ => continue,
+            };
+            let Some(base) = base else {
+                continue;
+            };
+
+            let mut p = base.clone();
+            for part in kp.path.split('\\').filter(|s| !s.is_empty()) {
+                p.push(part);
+            }
+            if exists(&p) {
+                target.entry(u.id.clone()).or_default().push(p);
+            }
+        }
+    }
+
+    out
+}
+
+fn matches_entry(known: &KnownApp, u: &UninstallEntry) -> bool {
+    let key = normalize_key(&strip_version_suffix(&u.name));
+    if key.is_empty() {
+        return false;
+    }
+
+    let name_hit = known.names.contains(&key)
+        || known
+            .name_prefixes
+            .iter()
+            .any(|p| key.starts_with(p.as_str()));
+    if !name_hit {
+        return false;
+    }
+
+    match known.publisher.as_deref() {
+        Some(expected) => u
+            .publisher
+            .as_deref()
+            .map(|p| normalize_key(p).contains(expected))
+            .unwrap_or(false),
+        None => true,
+    }
+}
+
+/// 签名库认领的整个顶层目录 key，启发式匹配不再参与竞争。
+/// 认领更深的路径（如 `Google\Chrome\User Data`）时顶层目录仍可被其他软件匹配，
+/// 见 [`nested_claims`] 与 [`carve_out`]。
+pub(super) fn claimed_folder_keys(
+    root_path: &Path,
+    known: &HashMap<String, Vec<PathBuf>>,
+) -> HashMap<String, String> {
+    let mut out = HashMap::new();
+    for (app_id, paths) in known {
+        for p in paths {
+            let Ok(rel) = p.strip_prefix(root_path) else {
+                continue;
+            };
+            let mut components = rel.components();
+            if let (Some(first), None) = (components.next(), components.next()) {
+                let key = first.as_os_str().to_string_lossy().to_lowercase();
+                out.entry(key).or_insert_with(|| app_id.clone());
+            }
+        }
+    }
+    out
+}
+
+/// 签名库在根目录下认领的、深于顶层目录的路径。
+pub(super) fn nested_claims(
+    root_path: &Path,
+    known: &HashMap<String, Vec<PathBuf>>,
+) -> Vec<PathBuf> {
+    known
+        .values()
+        .flatten()
+        .filter(|p| {
+            p.strip_prefix(root_path)
+                .is_ok_and(|rel| rel.components().count() > 1)
+        })
+        .cloned()
+        .collect()
+}
+
+/// 从 `folder` 中去掉已认领的子路径：沿认领路径逐层展开，返回其余的子目录。
+/// 展开路径上各层直接包含的文件很少，不再单独归属。
+pub(super) fn carve_out(folder: &Path, claimed: &[PathBuf]) -> Vec<PathBuf> {
+    let inner: Vec<PathBuf> = claimed
+        .iter()
+        .map(|c| lowercase_path(c))
+        .filter(|c| c.starts_with(lowercase_path(folder)))
+        .collect();
+    carve_out_lowercase(folder, &inner)
+}
+
+fn carve_out_lowercase(folder: &Path, inner: &[PathBuf]) -> Vec<PathBuf> {
+    let key = lowercase_path(folder);
+    if inner.contains(&key) {
+        return Vec::new();
+    }
+    if !inner.iter().any(|c| c.starts_with(&key)) {
+        return vec![folder.to_path_buf()];
+    }
+    // 读不到内容时无法展开，整个文件夹保留为一项，不让其余子目录从归属中消失。
+    let Ok(rd) = std::fs::read_dir(folder) else {
+        return vec![folder.to_path_buf()];
+    };
+    let mut out = Vec::new();
+    for e in rd.flatten() {
+        if e.file_type().is_ok_and(|t| t.is_dir()) {
+            out.extend(carve_out_lowercase(&e.path(), inner));
+        }
+    }
+    out.sort();
+    out
+}
+
+/// Windows 路径不区分大小写，签名库中的写法与磁盘上的可能不同。
+fn lowercase_path(p: &Path) -> PathBuf {
+    PathBuf::from(p.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("appmanager-known-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn bundled_database_parses() {
+        let file = parse_known_apps();
+        assert!(!file.apps.is_empty());
+        assert!(file.apps.iter().all(|a| !a.paths.is_empty()));
+    }
+
+    #[test]
+    fn deep_known_path_leaves_top_folder_open() {
+        let root = PathBuf::from("root");
+        let known = HashMap::from([
+            (
+                "chrome".to_string(),
+                vec![root.join("Google").join("Chrome").join("User Data")],
+            ),
+            ("discord".to_string(), vec![root.join("discord")]),
+        ]);
+
+        let claimed = claimed_folder_keys(&root, &known);
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed.get("discord").map(String::as_str), Some("discord"));
+        assert_eq!(
+            nested_claims(&root, &known),
+            vec![root.join("Google").join("Chrome").join("User Data")]
+        );
+    }
+
+    #[test]
+    fn carve_out_skips_claimed_subtree() {
+        let root = temp_root("carve");
+        let google = root.join("Google");
+        for dir in ["Chrome/User Data", "Chrome/Application", "DriveFS"] {
+            std::fs::create_dir_all(google.join(dir)).unwrap();
+        }
+
+        let claimed = vec![root.join("google").join("chrome").join("user data")];
+        let mut paths = carve_out(&google, &claimed);
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                google.join("Chrome").join("Application"),
+                google.join("DriveFS")
+            ]
+        );
+        assert_eq!(
+            carve_out(&google.join("DriveFS"), &claimed),
+            vec![google.join("DriveFS")]
+        );
+        assert!(carve_out(&google.join("Chrome").join("User Data"), &claimed).is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn unreadable_folder_is_kept_whole() {
+        let root = temp_root("unreadable");
+        let missing = root.join("Google");
+        let claimed = vec![missing.join("Chrome").join("User Data")];
+        assert_eq!(carve_out(&missing, &claimed), vec![missing.clone()]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
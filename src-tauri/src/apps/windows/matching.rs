@@ -1,22 +1,155 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use super::corrections::Correction;
+use super::known_apps::{carve_out, claimed_folder_keys, nested_claims};
+use super::publishers::{canonical_vendor, is_noise_token, vendor_of};
 use super::roots::{RootFolders, Roots};
-use super::uninstall::UninstallEntry;
+use super::uninstall::{strip_version_suffix, UninstallEntry};
+use super::version_info::FolderEvidence;
+use crate::apps::pe::VersionInfo;
+use crate::apps::{MatchParams, SharedSplit};
+
+/// 名称 token 命中时的最低得分（name * 100 + publisher）。
+const NAME_SCORE_THRESHOLD: i32 = 300;
+/// 仅凭厂商 token 匹配时的最低得分（publisher * 50）。
+const PUBLISHER_ONLY_SCORE_THRESHOLD: i32 = 200;
+
+impl Default for MatchParams {
+    fn default() -> Self {
+        MatchParams {
+            name_threshold: NAME_SCORE_THRESHOLD,
+            publisher_threshold: PUBLISHER_ONLY_SCORE_THRESHOLD,
+        }
+    }
+}
+
+/// 版本资源命中时的基础分，高于任何启发式 token 得分。
+const EVIDENCE_BASE_SCORE: i32 = 10_000;
+
+/// 用户修正过的「文件夹名 → 软件」配对得分，高于版本资源证据。
+const CORRECTION_SCORE: i32 = 20_000;
+
+/// 得分不低于最高分 3/4 的候选视为共同拥有该文件夹。
+const SHARED_SCORE_NUM: i32 = 3;
+const SHARED_SCORE_DEN: i32 = 4;
 
 #[derive(Clone)]
 pub(super) struct AppTokens {
     pub(super) app_id: String,
+    pub(super) app_name: String,
+    /// 去掉版本号后的规范名，用于跨机器识别同一软件。
+    pub(super) app_key: String,
+    pub(super) vendor_id: Option<String>,
     pub(super) name_tokens: Vec<String>,
     pub(super) publisher_tokens: Vec<String>,
     pub(super) allow_publisher_only: bool,
 }
 
+#[derive(Default)]
 pub(super) struct AssignedFolders {
     pub(super) local: HashMap<String, Vec<PathBuf>>,
     pub(super) roaming: HashMap<String, Vec<PathBuf>>,
     pub(super) local_low: HashMap<String, Vec<PathBuf>>,
     pub(super) program_data: HashMap<String, Vec<PathBuf>>,
+    /// 签名库中位于 AppData/ProgramData 之外的已知数据目录（如 Steam 游戏库）。
+    pub(super) extra: HashMap<String, Vec<PathBuf>>,
+    /// 多个软件得分相近、共同拥有的文件夹，不计入上面任何一个独占列表。
+    pub(super) shared: Vec<SharedFolder>,
+}
+
+impl AssignedFolders {
+    fn root_mut(&mut self, kind: &str) -> Option<&mut HashMap<String, Vec<PathBuf>>> {
+        match kind {
+            "appDataLocal" => Some(&mut self.local),
+            "appDataRoaming" => Some(&mut self.roaming),
+            "appDataLocalLow" => Some(&mut self.local_low),
+            "programData" => Some(&mut self.program_data),
+            _ => None,
+        }
+    }
+
+    /// 审计面板中的手动归属：`folder` 改为 `app_id` 独占，取代原有的独占或共享归属；
+    /// 其中已单独归属给其他软件的更深路径保持不变，只归属其余部分。
+    pub(super) fn assign_manually(&mut self, kind: &str, folder: &Path, app_id: &str) {
+        let folder_key = folder.to_string_lossy().to_lowercase();
+        let is_folder = |p: &PathBuf| p.to_string_lossy().to_lowercase() == folder_key;
+
+        self.shared.retain(|s| !is_folder(&s.path));
+        let shared_paths: Vec<PathBuf> = self.shared.iter().map(|s| s.path.clone()).collect();
+        let Some(map) = self.root_mut(kind) else {
+            return;
+        };
+        for paths in map.values_mut() {
+            paths.retain(|p| !is_folder(p));
+        }
+        map.retain(|_, paths| !paths.is_empty());
+
+        // carve_out 只关心 folder 之下的路径，其余的会被它忽略。
+        let others: Vec<PathBuf> = map
+            .iter()
+            .filter(|(id, _)| id.as_str() != app_id)
+            .flat_map(|(_, paths)| paths.iter().cloned())
+            .chain(shared_paths)
+            .collect();
+        let carved = carve_out(folder, &others);
+        // 自己原有的更深路径已被整体归属覆盖。
+        let owned = map.entry(app_id.to_string()).or_default();
+        owned.retain(|p| !Path::new(&p.to_string_lossy().to_lowercase()).starts_with(&folder_key));
+        owned.extend(carved);
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct SharedOwner {
+    pub(super) app_id: String,
+    pub(super) app_name: String,
+    pub(super) score: i32,
+}
+
+#[derive(Clone)]
+pub(super) struct SharedFolder {
+    pub(super) path: PathBuf,
+    pub(super) owners: Vec<SharedOwner>,
+}
+
+impl SharedFolder {
+    /// 按分摊方式计算 `app_id` 分得的字节数；各方份额之和恰好等于 `bytes`。
+    /// 指定的权重全为 0（或不是有限值）时改为平均分摊，不让文件夹的大小凭空消失。
+    pub(super) fn share_of(&self, app_id: &str, bytes: u64, split: &SharedSplit) -> u64 {
+        let mut weights: Vec<f64> = self
+            .owners
+            .iter()
+            .map(|o| match split {
+                SharedSplit::Even => 1.0,
+                SharedSplit::ByScore => o.score.max(1) as f64,
+                SharedSplit::Weights(w) => w.get(&o.app_id).copied().unwrap_or(1.0).max(0.0),
+            })
+            .collect();
+        let mut total: f64 = weights.iter().sum();
+        if !total.is_finite() || total <= 0.0 {
+            weights.fill(1.0);
+            total = weights.len() as f64;
+        }
+
+        let shares: Vec<u64> = weights
+            .iter()
+            .map(|w| (bytes as f64 * w / total).floor() as u64)
+            .collect();
+        let remainder = bytes.saturating_sub(shares.iter().sum());
+
+        self.owners
+            .iter()
+            .position(|o| o.app_id == app_id)
+            .map(|i| {
+                if i == 0 {
+                    shares[i] + remainder
+                } else {
+                    shares[i]
+                }
+            })
+            .unwrap_or(0)
+    }
 }
 
 pub(super) fn build_app_tokens(uninstall: &[UninstallEntry]) -> Vec<AppTokens> {
@@ -30,9 +163,17 @@ pub(super) fn build_app_tokens(uninstall: &[UninstallEntry]) -> Vec<AppTokens> {
                 .map(build_publisher_tokens)
                 .unwrap_or_default();
             let allow_publisher_only = name_tokens.is_empty();
+            let vendor_id = u
+                .publisher
+                .as_deref()
+                .and_then(vendor_of)
+                .map(|(id, _name)| id);
 
             AppTokens {
                 app_id: u.id.clone(),
+                app_name: u.name.clone(),
+                app_key: normalize_key(&strip_version_suffix(&u.name)),
+                vendor_id,
                 name_tokens,
                 publisher_tokens,
                 allow_publisher_only,
@@ -41,49 +182,247 @@ pub(super) fn build_app_tokens(uninstall: &[UninstallEntry]) -> Vec<AppTokens> {
         .collect()
 }
 
-pub(super) fn assign_folders(roots: &Roots, tokens: &[AppTokens]) -> AssignedFolders {
+/// 各根目录共用的匹配依据。
+struct MatchContext<'a> {
+    tokens: &'a [AppTokens],
+    corrections: &'a [Correction],
+    params: &'a MatchParams,
+}
+
+pub(super) fn assign_folders(
+    roots: &Roots,
+    tokens: &[AppTokens],
+    known: &AssignedFolders,
+    evidence: &FolderEvidence,
+    corrections: &[Correction],
+    params: &MatchParams,
+) -> AssignedFolders {
+    let ctx = MatchContext {
+        tokens,
+        corrections,
+        params,
+    };
+    let mut shared = Vec::new();
     AssignedFolders {
-        local: assign_for_root(roots.local.as_ref(), tokens),
-        roaming: assign_for_root(roots.roaming.as_ref(), tokens),
-        local_low: assign_for_root(roots.local_low.as_ref(), tokens),
-        program_data: assign_for_root(roots.program_data.as_ref(), tokens),
+        local: assign_for_root(
+            "appDataLocal",
+            roots.local.as_ref(),
+            &ctx,
+            &known.local,
+            &evidence.local,
+            &mut shared,
+        ),
+        roaming: assign_for_root(
+            "appDataRoaming",
+            roots.roaming.as_ref(),
+            &ctx,
+            &known.roaming,
+            &evidence.roaming,
+            &mut shared,
+        ),
+        local_low: assign_for_root(
+            "appDataLocalLow",
+            roots.local_low.as_ref(),
+            &ctx,
+            &known.local_low,
+            &evidence.local_low,
+            &mut shared,
+        ),
+        program_data: assign_for_root(
+            "programData",
+            roots.program_data.as_ref(),
+            &ctx,
+            &known.program_data,
+            &evidence.program_data,
+            &mut shared,
+        ),
+        extra: known.extra.clone(),
+        shared,
     }
 }
 
-fn assign_for_root(root: Option<&RootFolders>, tokens: &[AppTokens]) -> HashMap<String, Vec<PathBuf>> {
+fn assign_for_root(
+    kind: &str,
+    root: Option<&RootFolders>,
+    ctx: &MatchContext,
+    known: &HashMap<String, Vec<PathBuf>>,
+    evidence: &HashMap<String, Vec<VersionInfo>>,
+    shared: &mut Vec<SharedFolder>,
+) -> HashMap<String, Vec<PathBuf>> {
     let Some(root) = root else {
         return HashMap::new();
     };
 
-    let mut owners: HashMap<String, (i32, String)> = HashMap::new();
+    let claimed = claimed_folder_keys(&root.path, known);
+    let nested = nested_claims(&root.path, known);
+    let candidates = folder_candidates(kind, root, ctx, evidence, &claimed);
+    let tokens = ctx.tokens;
 
-    for app in tokens {
-        let candidates = build_candidate_folder_keys(app);
-        for c in candidates {
-            if !root.folders.contains_key(&c) {
-                continue;
+    let mut assigned = known.clone();
+    for (folder_key, cands) in candidates {
+        let Some(p) = root.folders.get(&folder_key) else {
+            continue;
+        };
+        // 签名库认领了其中更深的路径时，只归属其余部分，避免重复计算。
+        let paths = carve_out(p, &nested);
+        match plausible_owners(&cands) {
+            [(_score, i)] => {
+                assigned
+                    .entry(tokens[*i].app_id.clone())
+                    .or_default()
+                    .extend(paths);
             }
-            let score = score_folder(&c, app);
-            if score <= 0 {
+            owners => {
+                let owners: Vec<SharedOwner> = owners
+                    .iter()
+                    .map(|(score, i)| SharedOwner {
+                        app_id: tokens[*i].app_id.clone(),
+                        app_name: tokens[*i].app_name.clone(),
+                        score: *score,
+                    })
+                    .collect();
+                shared.extend(paths.into_iter().map(|path| SharedFolder {
+                    path,
+                    owners: owners.clone(),
+                }));
+            }
+        }
+    }
+
+    assigned
+}
+
+/// 每个文件夹的全部候选归属 `(得分, tokens 下标)`，按得分从高到低排列。
+/// 用户修正、版本资源证据与启发式 token 得分合并计算，签名库已认领的文件夹不参与。
+fn folder_candidates(
+    kind: &str,
+    root: &RootFolders,
+    ctx: &MatchContext,
+    evidence: &HashMap<String, Vec<VersionInfo>>,
+    claimed: &HashMap<String, String>,
+) -> HashMap<String, Vec<(i32, usize)>> {
+    let (tokens, corrections, params) = (ctx.tokens, ctx.corrections, ctx.params);
+    let mut out: HashMap<String, Vec<(i32, usize)>> = HashMap::new();
+
+    for (i, app) in tokens.iter().enumerate() {
+        for c in build_candidate_folder_keys(app) {
+            if !root.folders.contains_key(&c) || claimed.contains_key(&c) {
                 continue;
             }
-            match owners.get(&c) {
-                Some((best_score, _)) if *best_score >= score => {}
-                _ => {
-                    owners.insert(c, (score, app.app_id.clone()));
-                }
+            let score = score_folder(&c, app, params);
+            if score > 0 {
+                push_candidate(out.entry(c).or_default(), score, i);
             }
         }
     }
 
-    let mut assigned: HashMap<String, Vec<PathBuf>> = HashMap::new();
-    for (folder_key, (_score, app_id)) in owners {
-        if let Some(p) = root.folders.get(&folder_key) {
-            assigned.entry(app_id).or_default().push(p.clone());
+    for (folder_key, infos) in evidence {
+        if !root.folders.contains_key(folder_key) || claimed.contains_key(folder_key) {
+            continue;
+        }
+        for (i, app) in tokens.iter().enumerate() {
+            let score = score_evidence(infos, app);
+            if score > 0 {
+                push_candidate(out.entry(folder_key.clone()).or_default(), score, i);
+            }
         }
     }
 
-    assigned
+    // 修正按「根目录类型 + 文件夹名」记录，同一根目录下的同名文件夹在其他机器上同样生效。
+    for c in corrections.iter().filter(|c| c.root == kind) {
+        if !root.folders.contains_key(&c.folder_key) || claimed.contains_key(&c.folder_key) {
+            continue;
+        }
+        for (i, app) in tokens.iter().enumerate() {
+            if c.matches_app(app) {
+                push_candidate(
+                    out.entry(c.folder_key.clone()).or_default(),
+                    CORRECTION_SCORE,
+                    i,
+                );
+            }
+        }
+    }
+
+    for cands in out.values_mut() {
+        cands.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    }
+    out
+}
+
+fn push_candidate(cands: &mut Vec<(i32, usize)>, score: i32, app: usize) {
+    match cands.iter_mut().find(|(_, i)| *i == app) {
+        Some(existing) => existing.0 = existing.0.max(score),
+        None => cands.push((score, app)),
+    }
+}
+
+/// 得分接近最高分的候选都视为合理归属；只有一个时即独占该文件夹。
+pub(super) fn plausible_owners(cands: &[(i32, usize)]) -> &[(i32, usize)] {
+    let Some((best, _)) = cands.first() else {
+        return cands;
+    };
+    let n = cands
+        .iter()
+        .take_while(|(score, _)| score * SHARED_SCORE_DEN >= best * SHARED_SCORE_NUM)
+        .count();
+    &cands[..n]
+}
+
+pub(super) fn score_evidence(infos: &[VersionInfo], tokens: &AppTokens) -> i32 {
+    let Some(full_key) = tokens.name_tokens.first() else {
+        return 0;
+    };
+
+    let mut best = 0i32;
+    for info in infos {
+        let company_key = info
+            .company_name
+            .as_deref()
+            .map(normalize_key)
+            .unwrap_or_default();
+        let company_score: i32 = tokens
+            .publisher_tokens
+            .iter()
+            .filter(|t| company_key.contains(t.as_str()))
+            .map(|t| t.len() as i32)
+            .sum();
+
+        for product in [
+            info.product_name.as_deref(),
+            info.file_description.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let product_key = normalize_key(product);
+            if product_key.len() < 3 || is_generic_product(&product_key) {
+                continue;
+            }
+            let name_hit = tokens.name_tokens.contains(&product_key)
+                || (product_key.len() >= 4 && full_key.starts_with(&product_key));
+            if name_hit {
+                let score = EVIDENCE_BASE_SCORE + product_key.len() as i32 * 10 + company_score;
+                best = best.max(score);
+            }
+        }
+    }
+    best
+}
+
+fn is_generic_product(product_key: &str) -> bool {
+    matches!(
+        product_key,
+        "electron"
+            | "squirrel"
+            | "nodejs"
+            | "java"
+            | "python"
+            | "setup"
+            | "installer"
+            | "uninstaller"
+            | "microsoftwindowsoperatingsystem"
+    )
 }
 
 pub(super) fn build_candidate_folder_keys(tokens: &AppTokens) -> Vec<String> {
@@ -122,7 +461,7 @@ pub(super) fn build_candidate_folder_keys(tokens: &AppTokens) -> Vec<String> {
     out
 }
 
-pub(super) fn score_folder(folder_key: &str, tokens: &AppTokens) -> i32 {
+pub(super) fn score_folder(folder_key: &str, tokens: &AppTokens, params: &MatchParams) -> i32 {
     let mut name_score = 0i32;
     for t in &tokens.name_tokens {
         if folder_key.contains(t) {
@@ -139,7 +478,7 @@ pub(super) fn score_folder(folder_key: &str, tokens: &AppTokens) -> i32 {
 
     if name_score > 0 {
         let total = name_score * 100 + publisher_score;
-        if total >= 300 {
+        if total >= params.name_threshold {
             return total;
         }
         return 0;
@@ -147,7 +486,7 @@ pub(super) fn score_folder(folder_key: &str, tokens: &AppTokens) -> i32 {
 
     if tokens.allow_publisher_only && publisher_score > 0 {
         let total = publisher_score * 50;
-        if total >= 200 {
+        if total >= params.publisher_threshold {
             return total;
         }
     }
@@ -175,10 +514,15 @@ fn build_name_tokens(name: &str) -> Vec<String> {
 }
 
 fn build_publisher_tokens(publisher: &str) -> Vec<String> {
+    if let Some(vendor) = canonical_vendor(publisher) {
+        return vendor.tokens.clone();
+    }
+
     let mut tokens = Vec::new();
     for t in split_tokens(publisher) {
         if t.len() >= 4
             && !is_stop_token_publisher(&t)
+            && !is_noise_token(&t)
             && !t.chars().all(|c| c.is_ascii_digit())
         {
             if !tokens.contains(&t) {
@@ -205,7 +549,7 @@ pub(super) fn normalize_key(s: &str) -> String {
         .collect()
 }
 
-fn is_stop_token_name(token: &str) -> bool {
+pub(super) fn is_stop_token_name(token: &str) -> bool {
     matches!(
         token,
         "windows"
@@ -253,3 +597,109 @@ fn is_stop_token_publisher(token: &str) -> bool {
             | "solutions"
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("appmanager-matching-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn owner(app_id: &str) -> SharedOwner {
+        SharedOwner {
+            app_id: app_id.to_string(),
+            app_name: app_id.to_string(),
+            score: 10,
+        }
+    }
+
+    fn shared(owners: &[&str]) -> SharedFolder {
+        SharedFolder {
+            path: PathBuf::from("Shared"),
+            owners: owners.iter().map(|id| owner(id)).collect(),
+        }
+    }
+
+    #[test]
+    fn shares_add_up_to_the_folder_size() {
+        let folder = shared(&["a", "b", "c"]);
+        let weights = SharedSplit::Weights(HashMap::from([("a".to_string(), 2.0)]));
+        for split in [SharedSplit::Even, SharedSplit::ByScore, weights] {
+            let shares: Vec<u64> = ["a", "b", "c"]
+                .iter()
+                .map(|id| folder.share_of(id, 1001, &split))
+                .collect();
+            assert_eq!(shares.iter().sum::<u64>(), 1001);
+        }
+        assert_eq!(folder.share_of("a", 1000, &SharedSplit::Even), 334);
+        assert_eq!(folder.share_of("missing", 1000, &SharedSplit::Even), 0);
+    }
+
+    #[test]
+    fn zero_or_invalid_weights_fall_back_to_even_split() {
+        let folder = shared(&["a", "b"]);
+        let zero = SharedSplit::Weights(HashMap::from([
+            ("a".to_string(), 0.0),
+            ("b".to_string(), -1.0),
+        ]));
+        assert_eq!(folder.share_of("a", 1000, &zero), 500);
+        assert_eq!(folder.share_of("b", 1000, &zero), 500);
+
+        let infinite = SharedSplit::Weights(HashMap::from([("a".to_string(), f64::INFINITY)]));
+        assert_eq!(folder.share_of("b", 1000, &infinite), 500);
+    }
+
+    #[test]
+    fn manual_assignment_replaces_shared_and_exclusive_owners() {
+        let root = temp_root("manual");
+        let folder = root.join("Contoso");
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let mut assigned = AssignedFolders::default();
+        assigned.local.insert("old".into(), vec![folder.clone()]);
+        assigned.shared.push(SharedFolder {
+            path: folder.clone(),
+            owners: vec![owner("a"), owner("b")],
+        });
+
+        assigned.assign_manually("appDataLocal", &folder, "new");
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(assigned.shared.is_empty());
+        assert!(!assigned.local.contains_key("old"));
+        assert_eq!(assigned.local["new"], [folder]);
+    }
+
+    #[test]
+    fn manual_assignment_keeps_deeper_claims_of_other_apps() {
+        let root = temp_root("nested");
+        let folder = root.join("Google");
+        for sub in ["Chrome/User Data", "Chrome/Application", "Drive"] {
+            std::fs::create_dir_all(folder.join(sub)).unwrap();
+        }
+        let chrome_data = folder.join("Chrome").join("User Data");
+
+        let mut assigned = AssignedFolders::default();
+        assigned
+            .local
+            .insert("chrome".into(), vec![chrome_data.clone()]);
+        assigned.assign_manually("appDataLocal", &folder, "drive");
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(assigned.local["chrome"], [chrome_data]);
+        let mut drive = assigned.local["drive"].clone();
+        drive.sort();
+        assert_eq!(
+            drive,
+            [
+                folder.join("Chrome").join("Application"),
+                folder.join("Drive")
+            ]
+        );
+    }
+}
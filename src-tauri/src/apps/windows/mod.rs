@@ -1,13 +1,63 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, PoisonError, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant};
 
-use super::{AppBreakdownEntry, AppRecord, ScanProgress};
+use super::throttle::BackgroundPriority;
+use super::{
+    AppBreakdownEntry, AppRecord, CancelToken, PhaseTiming, ScanOptions, ScanProgress, WalkProgress,
+};
 
 mod audit;
+mod corrections;
+mod drill_down;
+mod duplicates;
+mod evaluate;
+mod file_categories;
+mod history;
+mod known_apps;
 mod matching;
+mod publishers;
 mod roots;
+mod size_index;
 mod sizing;
+mod storage;
 mod uninstall;
+mod version_info;
+mod watch;
+
+/// 扫描完成后保留的软件列表、根目录、归因结果与大小缓存，
+/// 增量更新、审计、逐层浏览与重复文件查找都基于它。
+pub(super) struct ScanSnapshot {
+    options: ScanOptions,
+    uninstall: Vec<uninstall::UninstallEntry>,
+    roots: roots::Roots,
+    /// 审计面板中的手动归属会直接更新它，无需重新扫描。
+    assigned: RwLock<matching::AssignedFolders>,
+    size_cache: sizing::SizeCache,
+    /// 含本次扫描在内的软件历史，审计据此找出已卸载软件的残留数据。
+    history: Vec<history::AppHistoryEntry>,
+    /// 每个软件最近一次的精确记录，按 id 索引；文件监视与手动归属更新后随之替换。
+    records: RwLock<HashMap<String, AppRecord>>,
+}
+
+impl ScanSnapshot {
+    fn assigned(&self) -> RwLockReadGuard<'_, matching::AssignedFolders> {
+        self.assigned.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn records(&self) -> RwLockReadGuard<'_, HashMap<String, AppRecord>> {
+        self.records.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn remember(&self, record: &AppRecord) {
+        self.records
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(record.id.clone(), record.clone());
+    }
+}
 
 pub(super) fn get_disk_info_windows() -> Vec<super::DiskInfo> {
     use sysinfo::Disks;
@@ -24,143 +74,754 @@ pub(super) fn get_disk_info_windows() -> Vec<super::DiskInfo> {
         .collect()
 }
 
-pub(super) fn audit_overview_windows() -> super::AuditOverview {
-    audit::audit_overview_windows()
+pub(super) fn audit_overview_windows(
+    snapshot: &ScanSnapshot,
+    options: &super::AuditOptions,
+) -> super::AuditOverview {
+    audit::audit_overview(snapshot, options)
+}
+
+pub(super) fn measure_audit_folder_size_windows(
+    snapshot: &ScanSnapshot,
+    kind: &str,
+    folder: &str,
+) -> Result<u64, String> {
+    audit::measure_folder_size(snapshot, kind, folder)
+}
+
+/// 记录修正并立即更新快照中的归属，返回该软件更新后的记录。
+pub(super) fn assign_folder_windows(
+    snapshot: &ScanSnapshot,
+    kind: &str,
+    folder: &str,
+    app_id: &str,
+) -> Result<AppRecord, String> {
+    let entry = snapshot
+        .uninstall
+        .iter()
+        .find(|u| u.id == app_id)
+        .ok_or_else(|| format!("未找到软件: {app_id}"))?;
+    let path = snapshot
+        .roots
+        .by_kind(kind)
+        .ok_or_else(|| format!("根目录 {kind} 不存在"))?
+        .folders
+        .get(&folder.to_lowercase())
+        .ok_or_else(|| format!("{kind} 下没有文件夹 {folder}"))?;
+
+    corrections::record_correction(kind, folder, entry)?;
+    snapshot
+        .assigned
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .assign_manually(kind, path, app_id);
+
+    let record = enrich_with_breakdown(
+        entry.clone(),
+        &snapshot.assigned(),
+        &snapshot.options,
+        &snapshot.size_cache,
+        sizing::SizePass::Exact,
+    );
+    snapshot.remember(&record);
+    Ok(record)
+}
+
+pub(super) fn export_corrections_windows() -> String {
+    corrections::export_corrections()
+}
+
+pub(super) fn import_corrections_windows(json: &str) -> Result<u32, String> {
+    corrections::import_corrections(json)
+}
+
+pub(super) fn size_index_info_windows() -> super::SizeIndexInfo {
+    size_index::size_index_info()
+}
+
+pub(super) fn clear_size_index_windows() -> Result<(), String> {
+    size_index::clear_size_index()
 }
 
-pub(super) fn measure_folder_size_windows(kind: &str, folder: &str) -> u64 {
-    audit::measure_folder_size_windows(kind, folder)
+pub(super) fn evaluate_matching_offline(
+    dataset: &super::MatchEvalDataset,
+    params: &super::MatchParams,
+) -> super::MatchEvalReport {
+    evaluate::evaluate_matching(dataset, params)
 }
 
-pub(super) fn scan_apps_windows() -> Vec<AppRecord> {
+pub(super) fn scan_apps_windows(options: &ScanOptions) -> Vec<AppRecord> {
     let mut out = Vec::new();
-    scan_apps_stream_windows(|_| {}, |r| out.push(r));
+    // 快速估算的临时记录随后会被精确记录取代，只保留后者。
+    let keep_exact = |r: AppRecord| {
+        if !r.estimated {
+            out.push(r);
+        }
+    };
+    scan_apps_stream_windows(options, &CancelToken::default(), |_| {}, keep_exact);
     out
 }
 
+pub(super) fn watch_apps_windows<F>(
+    snapshot: &ScanSnapshot,
+    options: &ScanOptions,
+    stop: &CancelToken,
+    on_update: F,
+) where
+    F: FnMut(AppRecord),
+{
+    watch::watch_snapshot(snapshot, options, stop, on_update)
+}
+
+pub(super) fn drill_down_windows(
+    snapshot: &ScanSnapshot,
+    app_id: &str,
+    kind: &str,
+    path: Option<&str>,
+    limit: Option<u32>,
+) -> Result<super::DrillDownLevel, String> {
+    drill_down::drill_down(snapshot, app_id, kind, path, limit)
+}
+
+pub(super) fn find_duplicates_windows(
+    snapshot: &ScanSnapshot,
+    options: &super::DuplicateOptions,
+    cancel: &CancelToken,
+    on_progress: impl FnMut(ScanProgress),
+) -> Option<super::DuplicateReport> {
+    duplicates::find_duplicates(snapshot, options, cancel, on_progress)
+}
+
 pub(super) fn scan_apps_stream_windows<FProgress, FRecord>(
+    options: &ScanOptions,
+    cancel: &CancelToken,
     mut on_progress: FProgress,
     mut on_record: FRecord,
-) where
+) -> Option<ScanSnapshot>
+where
     FProgress: FnMut(ScanProgress),
     FRecord: FnMut(AppRecord),
 {
+    let background = options.background.as_ref();
+    let _priority = background.map(|_| BackgroundPriority::enter());
+    let mut clock = PhaseClock::start();
     let mut uninstall = uninstall::scan_uninstall_entries();
     uninstall.sort_by(|a, b| a.name.cmp(&b.name));
     uninstall = uninstall::dedupe_uninstall_entries(uninstall);
+    version_info::fill_missing_publishers(&mut uninstall);
+    clock.finish("uninstall");
 
     on_progress(ScanProgress {
         phase: "uninstall".into(),
         current: 0,
         total: uninstall.len() as u32,
         message: "已识别安装软件列表".into(),
+        walk: None,
+        phase_timings: clock.timings(),
     });
 
     let roots = roots::build_roots();
     let app_tokens = matching::build_app_tokens(&uninstall);
-    let assigned = matching::assign_folders(&roots, &app_tokens);
-    let mut size_cache: HashMap<PathBuf, u64> = HashMap::new();
+    let known = known_apps::match_known_apps(&uninstall, &roots);
+    let evidence = version_info::collect_folder_evidence(&roots);
+    let corrections = corrections::load_corrections();
+    let assigned = matching::assign_folders(
+        &roots,
+        &app_tokens,
+        &known,
+        &evidence,
+        &corrections,
+        &super::MatchParams::default(),
+    );
+    if cancel.is_cancelled() {
+        return None;
+    }
+    // 注册表没有登记大小的软件需要遍历安装目录，与各根目录一样整体并行遍历一次。
+    let install_dirs = uninstall
+        .iter()
+        .filter(|u| u.estimated_bytes == 0)
+        .filter_map(uninstall::get_install_dir_hint);
+    let walk_roots = roots.walk_paths(install_dirs);
+    let size_cache = sizing::SizeCache::with_persistent_index(cancel.clone(), walk_roots)
+        .with_link_policy(options.link_policy)
+        .with_background(background);
+    clock.finish("match");
 
     let total = uninstall.len().max(1) as u32;
-    for (i, u) in uninstall.into_iter().enumerate() {
-        let record = enrich_with_breakdown(u, &assigned, &mut size_cache);
-        on_record(record);
-        on_progress(ScanProgress {
-            phase: "scan".into(),
-            current: (i as u32).saturating_add(1),
-            total,
-            message: "正在分析占用细节…".into(),
-        });
+
+    // 第一遍只用注册表估算值、上次的索引与有限遍历，几秒内先给出全部软件的近似大小。
+    for u in &uninstall {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        on_record(enrich_with_breakdown(
+            u.clone(),
+            &assigned,
+            options,
+            &size_cache,
+            sizing::SizePass::Estimate,
+        ));
     }
+    clock.finish("estimate");
+    on_progress(ScanProgress {
+        phase: "estimate".into(),
+        current: 0,
+        total,
+        message: "已给出估算大小，正在精确统计…".into(),
+        walk: None,
+        phase_timings: clock.timings(),
+    });
+
+    let mut order: Vec<usize> = (0..uninstall.len()).collect();
+    if options.largest_first {
+        order.sort_by_key(|&i| std::cmp::Reverse(uninstall[i].estimated_bytes));
+    }
+
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel::<AppRecord>();
+    let mut records = HashMap::new();
+    std::thread::scope(|scope| {
+        // 后台模式只用一个线程，限速以外也尽量少占用磁盘队列。
+        let workers = if background.is_some() {
+            1
+        } else {
+            sizing_workers()
+        };
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (order, next, uninstall, assigned, size_cache) =
+                (&order, &next, &uninstall, &assigned, &size_cache);
+            scope.spawn(move || {
+                let _priority = background.map(|_| BackgroundPriority::enter());
+                loop {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    let Some(&i) = order.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    let record = enrich_with_breakdown(
+                        uninstall[i].clone(),
+                        assigned,
+                        options,
+                        size_cache,
+                        sizing::SizePass::Exact,
+                    );
+                    if tx.send(record).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // 回调不要求跨线程，统一在调用线程里按完成顺序发送；
+        // 单个大目录可能遍历很久，期间定期汇报遍历进度。
+        let walk = WalkMeter::start(&size_cache);
+        let mut completed = 0u32;
+        loop {
+            let record = match rx.recv_timeout(WALK_PROGRESS_INTERVAL) {
+                Ok(record) => Some(record),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            // 取消时目录遍历会提前返回，之后完成的记录不完整，不再发送。
+            if cancel.is_cancelled() {
+                continue;
+            }
+            if let Some(record) = record {
+                completed = completed.saturating_add(1);
+                records.insert(record.id.clone(), record.clone());
+                on_record(record);
+            }
+            on_progress(ScanProgress {
+                phase: "scan".into(),
+                current: completed,
+                total,
+                message: if cancel.is_paused() {
+                    "已暂停".into()
+                } else {
+                    "正在分析占用细节…".into()
+                },
+                walk: Some(walk.progress(&size_cache)),
+                phase_timings: clock.timings(),
+            });
+        }
+    });
+    if cancel.is_cancelled() {
+        return None;
+    }
+    clock.finish("scan");
+
+    // 索引写入失败只影响下次扫描速度，不影响本次结果。
+    let _ = size_cache.save_index();
+    let history = history::update_app_history(&uninstall, &assigned);
+    clock.finish("save");
 
     on_progress(ScanProgress {
         phase: "done".into(),
         current: total,
         total,
         message: "扫描完成".into(),
+        walk: None,
+        phase_timings: clock.timings(),
     });
+
+    Some(ScanSnapshot {
+        options: options.clone(),
+        uninstall,
+        roots,
+        assigned: RwLock::new(assigned),
+        size_cache,
+        history,
+        records: RwLock::new(records),
+    })
+}
+
+/// 精确统计期间汇报遍历进度的间隔。
+const WALK_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 记录各阶段耗时，每个阶段从上一阶段结束时开始计。
+struct PhaseClock {
+    last: Instant,
+    timings: Vec<PhaseTiming>,
+}
+
+impl PhaseClock {
+    fn start() -> PhaseClock {
+        PhaseClock {
+            last: Instant::now(),
+            timings: Vec::new(),
+        }
+    }
+
+    fn finish(&mut self, phase: &str) {
+        let now = Instant::now();
+        self.timings.push(PhaseTiming {
+            phase: phase.into(),
+            millis: now.duration_since(self.last).as_millis() as u64,
+        });
+        self.last = now;
+    }
+
+    fn timings(&self) -> Vec<PhaseTiming> {
+        self.timings.clone()
+    }
+}
+
+/// 从精确统计开始时起算的遍历速度；估算阶段的抽样读取不计入。
+struct WalkMeter {
+    started: Instant,
+    base_files: u64,
+    base_bytes: u64,
+}
+
+impl WalkMeter {
+    fn start(cache: &sizing::SizeCache) -> WalkMeter {
+        let (base_files, base_bytes, _) = cache.walk_stats().snapshot();
+        WalkMeter {
+            started: Instant::now(),
+            base_files,
+            base_bytes,
+        }
+    }
+
+    fn progress(&self, cache: &sizing::SizeCache) -> WalkProgress {
+        let (files, bytes, current) = cache.walk_stats().snapshot();
+        let files_seen = files.saturating_sub(self.base_files);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let files_per_sec = if elapsed > 0.0 {
+            files_seen as f64 / elapsed
+        } else {
+            0.0
+        };
+        // 上次的目录项总数只是参考：超出后不再给出剩余时间，而不是显示 0。
+        let expected = cache.expected_entries();
+        let eta_secs = (files_per_sec > 0.0 && files_seen < expected)
+            .then(|| ((expected - files_seen) as f64 / files_per_sec).ceil() as u64);
+        WalkProgress {
+            files_seen,
+            bytes_seen: bytes.saturating_sub(self.base_bytes),
+            current_path: current.to_string_lossy().to_string(),
+            files_per_sec,
+            eta_secs,
+        }
+    }
+}
+
+/// 目录统计以磁盘 I/O 为主，线程过多反而互相抢占，因此设上限。
+const MAX_SIZING_WORKERS: usize = 4;
+
+fn sizing_workers() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_SIZING_WORKERS)
 }
 
 fn enrich_with_breakdown(
     uninstall: uninstall::UninstallEntry,
     assigned: &matching::AssignedFolders,
-    size_cache: &mut HashMap<PathBuf, u64>,
+    options: &ScanOptions,
+    size_cache: &sizing::SizeCache,
+    pass: sizing::SizePass,
 ) -> AppRecord {
+    let estimated = pass == sizing::SizePass::Estimate;
+    // 用户选定的条目类型以抽样统计代替精确统计。
+    let pass_for = |kind: &str| {
+        if pass == sizing::SizePass::Exact && options.approximate.iter().any(|k| k == kind) {
+            sizing::SizePass::Sampled
+        } else {
+            pass
+        }
+    };
+    let error_for = |kind: &str, size: &sizing::DirSize| {
+        (pass_for(kind) == sizing::SizePass::Sampled).then(|| size.error_bound())
+    };
     let mut breakdown = Vec::new();
 
-    let (program_bytes, program_paths, program_label) = if uninstall.estimated_bytes > 0 {
+    let (program_size, program_paths, program_label) = if uninstall.estimated_bytes > 0 {
+        // 注册表估算值无法区分分配大小，两者取同一值。
         (
-            uninstall.estimated_bytes,
+            sizing::DirSize {
+                apparent: uninstall.estimated_bytes,
+                allocated: uninstall.estimated_bytes,
+                categories: Default::default(),
+                last_activity: uninstall::get_install_dir_hint(&uninstall)
+                    .map(|d| sizing::shallow_activity(&d))
+                    .unwrap_or(0),
+                variance: 0,
+                skipped: 0,
+            },
             Vec::new(),
             "软件程序 (系统估算)".to_string(),
         )
     } else {
-        let (bytes, paths) = sizing::compute_install_bytes(&uninstall, size_cache);
-        (bytes, paths, "软件程序 (目录扫描)".to_string())
+        let (size, paths) =
+            sizing::compute_install_bytes(&uninstall, size_cache, pass_for("program"));
+        (size, paths, "软件程序 (目录扫描)".to_string())
+    };
+
+    let walked_install_dir = if uninstall.estimated_bytes > 0 {
+        None
+    } else {
+        uninstall::get_install_dir_hint(&uninstall)
     };
 
     breakdown.push(AppBreakdownEntry {
         kind: "program".into(),
         label: program_label,
-        bytes: program_bytes,
+        bytes: program_size.apparent,
+        allocated_bytes: program_size.allocated,
         paths: program_paths,
+        shared_owners: Vec::new(),
+        categories: program_size.categories.entries(),
+        last_activity: activity(program_size.last_activity),
+        error_bytes: if uninstall.estimated_bytes > 0 {
+            None
+        } else {
+            error_for("program", &program_size)
+        },
+        skipped_count: program_size.skipped,
+        relocated: walked_install_dir
+            .as_ref()
+            .map(|d| size_cache.relocated_under(std::slice::from_ref(d)))
+            .unwrap_or_default(),
+        estimated,
     });
 
     if let Some(paths) = assigned.local.get(&uninstall.id) {
-        let (bytes, shown) = sizing::sum_paths(paths, size_cache);
-        if bytes > 0 {
+        let (size, shown) = sizing::sum_paths(paths, size_cache, pass_for("appDataLocal"));
+        if size.apparent > 0 || size.skipped > 0 {
             breakdown.push(AppBreakdownEntry {
                 kind: "appDataLocal".into(),
                 label: "应用数据 (AppData/Local)".into(),
-                bytes,
+                bytes: size.apparent,
+                allocated_bytes: size.allocated,
                 paths: shown,
+                shared_owners: Vec::new(),
+                categories: size.categories.entries(),
+                last_activity: activity(size.last_activity),
+                error_bytes: error_for("appDataLocal", &size),
+                skipped_count: size.skipped,
+                relocated: size_cache.relocated_under(paths),
+                estimated,
             });
         }
     }
 
     if let Some(paths) = assigned.roaming.get(&uninstall.id) {
-        let (bytes, shown) = sizing::sum_paths(paths, size_cache);
-        if bytes > 0 {
+        let (size, shown) = sizing::sum_paths(paths, size_cache, pass_for("appDataRoaming"));
+        if size.apparent > 0 || size.skipped > 0 {
             breakdown.push(AppBreakdownEntry {
                 kind: "appDataRoaming".into(),
                 label: "应用数据 (AppData/Roaming)".into(),
-                bytes,
+                bytes: size.apparent,
+                allocated_bytes: size.allocated,
                 paths: shown,
+                shared_owners: Vec::new(),
+                categories: size.categories.entries(),
+                last_activity: activity(size.last_activity),
+                error_bytes: error_for("appDataRoaming", &size),
+                skipped_count: size.skipped,
+                relocated: size_cache.relocated_under(paths),
+                estimated,
             });
         }
     }
 
     if let Some(paths) = assigned.local_low.get(&uninstall.id) {
-        let (bytes, shown) = sizing::sum_paths(paths, size_cache);
-        if bytes > 0 {
+        let (size, shown) = sizing::sum_paths(paths, size_cache, pass_for("appDataLocalLow"));
+        if size.apparent > 0 || size.skipped > 0 {
             breakdown.push(AppBreakdownEntry {
                 kind: "appDataLocalLow".into(),
                 label: "应用数据 (AppData/LocalLow)".into(),
-                bytes,
+                bytes: size.apparent,
+                allocated_bytes: size.allocated,
                 paths: shown,
+                shared_owners: Vec::new(),
+                categories: size.categories.entries(),
+                last_activity: activity(size.last_activity),
+                error_bytes: error_for("appDataLocalLow", &size),
+                skipped_count: size.skipped,
+                relocated: size_cache.relocated_under(paths),
+                estimated,
             });
         }
     }
 
     if let Some(paths) = assigned.program_data.get(&uninstall.id) {
-        let (bytes, shown) = sizing::sum_paths(paths, size_cache);
-        if bytes > 0 {
+        let (size, shown) = sizing::sum_paths(paths, size_cache, pass_for("programData"));
+        if size.apparent > 0 || size.skipped > 0 {
             breakdown.push(AppBreakdownEntry {
                 kind: "programData".into(),
                 label: "共享数据 (ProgramData)".into(),
-                bytes,
+                bytes: size.apparent,
+                allocated_bytes: size.allocated,
+                paths: shown,
+                shared_owners: Vec::new(),
+                categories: size.categories.entries(),
+                last_activity: activity(size.last_activity),
+                error_bytes: error_for("programData", &size),
+                skipped_count: size.skipped,
+                relocated: size_cache.relocated_under(paths),
+                estimated,
+            });
+        }
+    }
+
+    if let Some(paths) = assigned.extra.get(&uninstall.id) {
+        let paths: Vec<PathBuf> = paths
+            .iter()
+            .filter(|p| {
+                !walked_install_dir
+                    .as_ref()
+                    .is_some_and(|d| p.starts_with(d))
+            })
+            .cloned()
+            .collect();
+        let (size, shown) = sizing::sum_paths(&paths, size_cache, pass_for("extraData"));
+        if size.apparent > 0 || size.skipped > 0 {
+            breakdown.push(AppBreakdownEntry {
+                kind: "extraData".into(),
+                label: "附加数据 (已知目录)".into(),
+                bytes: size.apparent,
+                allocated_bytes: size.allocated,
                 paths: shown,
+                shared_owners: Vec::new(),
+                categories: size.categories.entries(),
+                last_activity: activity(size.last_activity),
+                error_bytes: error_for("extraData", &size),
+                skipped_count: size.skipped,
+                relocated: size_cache.relocated_under(&paths),
+                estimated,
             });
         }
     }
 
+    let mut shared_bytes = 0u64;
+    let mut shared_allocated = 0u64;
+    let mut shared_categories = file_categories::CategorySizes::default();
+    let mut shared_activity = 0u64;
+    let mut shared_variance = 0u128;
+    let mut shared_skipped = 0u32;
+    let mut shared_paths: Vec<PathBuf> = Vec::new();
+    let mut shared_items: Vec<(u64, String)> = Vec::new();
+    let mut shared_owners: Vec<String> = Vec::new();
+    for folder in assigned
+        .shared
+        .iter()
+        .filter(|f| f.owners.iter().any(|o| o.app_id == uninstall.id))
+    {
+        let (folder_size, _) = sizing::sum_paths(
+            std::slice::from_ref(&folder.path),
+            size_cache,
+            pass_for("shared"),
+        );
+        let share = folder.share_of(&uninstall.id, folder_size.apparent, &options.shared_split);
+        let allocated_share =
+            folder.share_of(&uninstall.id, folder_size.allocated, &options.shared_split);
+        shared_bytes = shared_bytes.saturating_add(share);
+        shared_allocated = shared_allocated.saturating_add(allocated_share);
+        shared_categories = shared_categories.merge(
+            folder_size
+                .categories
+                .map(|b| folder.share_of(&uninstall.id, b, &options.shared_split)),
+        );
+        shared_activity = shared_activity.max(folder_size.last_activity);
+        shared_skipped = shared_skipped.saturating_add(folder_size.skipped);
+        shared_paths.push(folder.path.clone());
+        if folder_size.apparent > 0 {
+            let ratio = share as f64 / folder_size.apparent as f64;
+            shared_variance += (folder_size.variance as f64 * ratio * ratio) as u128;
+        }
+        shared_items.push((share, folder.path.to_string_lossy().to_string()));
+        for o in &folder.owners {
+            if !shared_owners.contains(&o.app_name) {
+                shared_owners.push(o.app_name.clone());
+            }
+        }
+    }
+    if shared_bytes > 0 || shared_skipped > 0 {
+        shared_items.sort_by_key(|(bytes, _)| std::cmp::Reverse(*bytes));
+        breakdown.push(AppBreakdownEntry {
+            kind: "shared".into(),
+            label: "共享数据 (多软件共用)".into(),
+            bytes: shared_bytes,
+            allocated_bytes: shared_allocated,
+            paths: shared_items.into_iter().take(5).map(|(_, p)| p).collect(),
+            shared_owners,
+            categories: shared_categories.entries(),
+            last_activity: activity(shared_activity),
+            error_bytes: error_for(
+                "shared",
+                &sizing::DirSize {
+                    variance: shared_variance,
+                    ..Default::default()
+                },
+            ),
+            skipped_count: shared_skipped,
+            relocated: size_cache.relocated_under(&shared_paths),
+            estimated,
+        });
+    }
+
     let total_bytes = breakdown.iter().map(|b| b.bytes).sum();
+    let total_allocated_bytes = breakdown.iter().map(|b| b.allocated_bytes).sum();
+    let last_activity = breakdown.iter().filter_map(|b| b.last_activity).max();
+    // 各条目的估算相互独立，合并误差按方差相加：取平方和的平方根。
+    let error_bytes = breakdown.iter().any(|b| b.error_bytes.is_some()).then(|| {
+        let sum: f64 = breakdown
+            .iter()
+            .filter_map(|b| b.error_bytes)
+            .map(|e| (e as f64).powi(2))
+            .sum();
+        sum.sqrt() as u64
+    });
+    let incomplete = breakdown.iter().any(|b| b.skipped_count > 0);
+    let followed_volumes = followed_volumes(&breakdown);
+
+    let (vendor_id, vendor_name) = uninstall
+        .publisher
+        .as_deref()
+        .and_then(publishers::vendor_of)
+        .unzip();
 
     AppRecord {
         id: uninstall.id,
         name: uninstall.name,
         publisher: uninstall.publisher,
+        vendor_id,
+        vendor_name,
         total_bytes,
+        total_allocated_bytes,
         breakdown,
+        last_activity,
+        error_bytes,
+        incomplete,
+        followed_volumes,
+        estimated,
+    }
+}
+
+/// 各条目中跟随的链接目标按卷汇总；多个链接指向同一目标时只计一次。
+fn followed_volumes(breakdown: &[AppBreakdownEntry]) -> Vec<super::VolumeBytes> {
+    let mut targets: Vec<&super::RelocatedFolder> = breakdown
+        .iter()
+        .flat_map(|b| &b.relocated)
+        .filter(|r| r.bytes.is_some())
+        .collect();
+    targets.sort_by(|a, b| a.target.cmp(&b.target));
+    targets.dedup_by(|a, b| a.target == b.target);
+
+    let mut out: Vec<super::VolumeBytes> = Vec::new();
+    for r in targets {
+        let bytes = r.bytes.unwrap_or(0);
+        match out.iter_mut().find(|v| v.volume == r.volume) {
+            Some(v) => v.bytes = v.bytes.saturating_add(bytes),
+            None => out.push(super::VolumeBytes {
+                volume: r.volume.clone(),
+                bytes,
+            }),
+        }
+    }
+    out.sort_by(|a, b| a.volume.cmp(&b.volume));
+    out
+}
+
+/// 0 表示未能读取任何时间。
+fn activity(secs: u64) -> Option<u64> {
+    (secs > 0).then_some(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::RelocatedFolder;
+
+    fn entry(relocated: Vec<RelocatedFolder>) -> AppBreakdownEntry {
+        AppBreakdownEntry {
+            kind: "program".into(),
+            label: String::new(),
+            bytes: 0,
+            allocated_bytes: 0,
+            paths: Vec::new(),
+            shared_owners: Vec::new(),
+            categories: Vec::new(),
+            last_activity: None,
+            error_bytes: None,
+            skipped_count: 0,
+            relocated,
+            estimated: false,
+        }
+    }
+
+    fn link(path: &str, target: &str, volume: &str, bytes: Option<u64>) -> RelocatedFolder {
+        RelocatedFolder {
+            path: path.into(),
+            target: target.into(),
+            volume: volume.into(),
+            bytes,
+        }
+    }
+
+    #[test]
+    fn followed_targets_are_summed_per_volume_once() {
+        let breakdown = [
+            entry(vec![
+                link(r"C:\App\Cache", r"D:\Cache", "D:", Some(100)),
+                link(r"C:\App\Media", r"E:\Media", "E:", Some(7)),
+            ]),
+            entry(vec![
+                link(r"C:\Data\Cache", r"D:\Cache", "D:", Some(100)),
+                link(r"C:\Data\Logs", r"D:\Logs", "D:", Some(20)),
+                link(r"C:\Data\Skipped", r"F:\Skipped", "F:", None),
+            ]),
+        ];
+        let volumes: Vec<(String, u64)> = followed_volumes(&breakdown)
+            .into_iter()
+            .map(|v| (v.volume, v.bytes))
+            .collect();
+        assert_eq!(volumes, [("D:".to_string(), 120), ("E:".to_string(), 7)]);
     }
 }
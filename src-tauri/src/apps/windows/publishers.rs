@@ -0,0 +1,124 @@
+use std::sync::OnceLock;
+
+/// 厂商别名与法律后缀归一化表，随程序一起发布。
+const PUBLISHERS_JSON: &str = include_str!("../../../data/publishers.json");
+const PUBLISHERS_VERSION: u32 = 1;
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishersFile {
+    version: u32,
+    legal_suffixes: Vec<String>,
+    noise_words: Vec<String>,
+    vendors: Vec<Vendor>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Vendor {
+    pub(super) id: String,
+    pub(super) name: String,
+    aliases: Vec<String>,
+    /// 用于目录匹配的 token；为空表示该厂商过于宽泛，不参与厂商匹配。
+    #[serde(default)]
+    pub(super) tokens: Vec<String>,
+}
+
+/// 厂商表随程序编译进来，解析失败是打包错误，不能静默退化为空表。
+fn parse_publishers() -> PublishersFile {
+    let file: PublishersFile =
+        serde_json::from_str(PUBLISHERS_JSON).expect("内置的 publishers.json 格式错误");
+    assert_eq!(
+        file.version, PUBLISHERS_VERSION,
+        "内置的 publishers.json 版本不匹配"
+    );
+    file
+}
+
+fn table() -> &'static PublishersFile {
+    static CACHE: OnceLock<PublishersFile> = OnceLock::new();
+    CACHE.get_or_init(parse_publishers)
+}
+
+pub(super) fn is_noise_token(token: &str) -> bool {
+    let t = table();
+    t.legal_suffixes.iter().any(|s| s == token) || t.noise_words.iter().any(|s| s == token)
+}
+
+/// 去掉法律后缀与泛化词后的厂商 key；保留非 ASCII 字符以支持本地化名称。
+pub(super) fn vendor_key(publisher: &str) -> String {
+    let t = table();
+    let words: Vec<String> = publisher
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(|c| c.to_lowercase())
+                .collect::<String>()
+        })
+        .filter(|w| !w.is_empty() && !t.legal_suffixes.contains(w))
+        .collect();
+
+    let significant: String = words
+        .iter()
+        .filter(|w| !t.noise_words.contains(w))
+        .map(String::as_str)
+        .collect();
+    let mut key = if significant.is_empty() {
+        words.concat()
+    } else {
+        significant
+    };
+
+    for suffix in t.legal_suffixes.iter().filter(|s| !s.is_ascii()) {
+        if key.len() > suffix.len() {
+            if let Some(stripped) = key.strip_suffix(suffix.as_str()) {
+                key = stripped.to_string();
+            } else if let Some(stripped) = key.strip_prefix(suffix.as_str()) {
+                key = stripped.to_string();
+            }
+        }
+    }
+    key
+}
+
+pub(super) fn canonical_vendor(publisher: &str) -> Option<&'static Vendor> {
+    let key = vendor_key(publisher);
+    if key.is_empty() {
+        return None;
+    }
+    table().vendors.iter().find(|v| v.aliases.contains(&key))
+}
+
+/// 厂商 (id, 展示名)：命中归一化表时使用规范厂商，否则退回到去后缀后的 key。
+pub(super) fn vendor_of(publisher: &str) -> Option<(String, String)> {
+    if let Some(v) = canonical_vendor(publisher) {
+        return Some((v.id.clone(), v.name.clone()));
+    }
+    let key = vendor_key(publisher);
+    if key.is_empty() {
+        return None;
+    }
+    Some((key, publisher.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_table_parses() {
+        let file = parse_publishers();
+        assert!(!file.legal_suffixes.is_empty());
+        assert!(!file.vendors.is_empty());
+    }
+
+    #[test]
+    fn legal_suffixes_are_stripped() {
+        assert_eq!(vendor_key("Microsoft Corporation"), "microsoft");
+        assert_eq!(
+            canonical_vendor("Advanced Micro Devices, Inc.").map(|v| v.id.as_str()),
+            Some("amd")
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone)]
 pub(super) struct RootFolders {
+    pub(super) path: PathBuf,
     pub(super) folders: HashMap<String, PathBuf>,
 }
 
@@ -54,5 +55,115 @@ fn list_root_folders(root: PathBuf) -> RootFolders {
             folders.insert(key, p);
         }
     }
-    RootFolders { folders }
+    RootFolders {
+        path: root,
+        folders,
+    }
+}
+
+impl Roots {
+    /// 按审计报告中的根目录类型（`appDataLocal` 等）取根目录。
+    pub(super) fn by_kind(&self, kind: &str) -> Option<&RootFolders> {
+        match kind {
+            "appDataLocal" => self.local.as_ref(),
+            "appDataRoaming" => self.roaming.as_ref(),
+            "appDataLocalLow" => self.local_low.as_ref(),
+            "programData" => self.program_data.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// 存在的根目录路径，供监听目录变化使用。
+    pub(super) fn paths(&self) -> Vec<PathBuf> {
+        [
+            &self.local,
+            &self.roaming,
+            &self.local_low,
+            &self.program_data,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|r| r.path.clone())
+        .collect()
+    }
+
+    /// 大小统计时各自整体并行遍历一次的目录：各根目录，加上需要遍历的安装目录。
+    /// 安装目录去掉重复与嵌套的，只保留最外层；位于根目录之下或包含根目录的不单独遍历，
+    /// 前者已在根目录中统计，后者多半是登记错误（如整个盘符），仍按需单独统计。
+    pub(super) fn walk_paths(
+        &self,
+        install_dirs: impl IntoIterator<Item = PathBuf>,
+    ) -> Vec<PathBuf> {
+        let roots = self.paths();
+        let root_keys: Vec<PathBuf> = roots.iter().map(|p| path_key(p)).collect();
+        let mut dirs: Vec<(PathBuf, PathBuf)> = install_dirs
+            .into_iter()
+            .map(|p| (path_key(&p), p))
+            .filter(|(key, _)| {
+                !root_keys
+                    .iter()
+                    .any(|r| key.starts_with(r) || r.starts_with(key))
+            })
+            .collect();
+        // 按小写路径排序后，嵌套的目录紧跟在其外层目录之后。
+        dirs.sort();
+        let mut outermost: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for (key, dir) in dirs {
+            if outermost
+                .last()
+                .is_some_and(|(outer, _)| key.starts_with(outer))
+            {
+                continue;
+            }
+            outermost.push((key, dir));
+        }
+        roots
+            .into_iter()
+            .chain(outermost.into_iter().map(|(_, dir)| dir))
+            .collect()
+    }
+}
+
+/// 路径比较不区分大小写，与 Windows 文件系统一致。
+fn path_key(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_paths_keeps_outermost_install_dirs_outside_roots() {
+        let root = PathBuf::from("/users/me/appdata/local");
+        let roots = Roots {
+            local: Some(RootFolders {
+                path: root.clone(),
+                folders: HashMap::new(),
+            }),
+            roaming: None,
+            local_low: None,
+            program_data: None,
+        };
+        let install_dirs = [
+            "/program files/Foo",
+            "/Program Files/foo/bin",
+            "/program files/foo",
+            "/program files/foobar",
+            "/users/me/appdata/local/programs/bar",
+            "/users",
+        ]
+        .map(PathBuf::from);
+
+        let paths = roots.walk_paths(install_dirs);
+        assert_eq!(
+            paths,
+            [
+                root,
+                PathBuf::from("/program files/Foo"),
+                PathBuf::from("/program files/foobar")
+            ]
+        );
+        assert_eq!(roots.paths().len(), 1);
+    }
 }
@@ -0,0 +1,377 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::file_categories::{CategorySizes, FileCategory};
+use super::sizing::SkipReason;
+use super::storage::{file_path, read_json, remove_file, write_json};
+use crate::apps::SizeIndexInfo;
+
+const SIZE_INDEX_FILE: &str = "size_index.json";
+const SIZE_INDEX_VERSION: u32 = 5;
+
+/// 即使元数据都未变化，记录超过该时长后也重新统计一次，防止文件系统未更新 mtime 的情况。
+const MAX_RECORD_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// 复用记录前比较的目录元数据。目录 mtime 只在增删、重命名子项时变化，
+/// 文件原地改写（日志、数据库、缓存）不会反映出来，因此还比较其中文件最新的 mtime 与总大小；
+/// 这些只需读取每个文件的元数据，不必打开文件。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct DirStamp {
+    pub(super) mtime: u64,
+    pub(super) entry_count: u32,
+    pub(super) newest_file_mtime: u64,
+    pub(super) file_bytes: u64,
+    /// 文件最近一次修改或访问的时间，Unix 秒；复用记录时以它更新 `last_activity`。
+    pub(super) last_activity: u64,
+}
+
+impl DirStamp {
+    pub(super) fn of_files<'a>(
+        mtime: u64,
+        entry_count: u32,
+        files: impl IntoIterator<Item = &'a std::fs::Metadata>,
+    ) -> DirStamp {
+        files.into_iter().fold(
+            DirStamp {
+                mtime,
+                entry_count,
+                ..DirStamp::default()
+            },
+            |acc, m| DirStamp {
+                newest_file_mtime: acc.newest_file_mtime.max(mtime_nanos(m)),
+                file_bytes: acc.file_bytes.saturating_add(m.len()),
+                last_activity: acc.last_activity.max(activity_secs(m)),
+                ..acc
+            },
+        )
+    }
+}
+
+/// 存在多个硬链接的文件单独记录，复用索引时仍能在一次扫描内去重。
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct LinkedFile {
+    pub(super) volume: u64,
+    pub(super) file_id: u64,
+    pub(super) apparent: u64,
+    pub(super) allocated: u64,
+    pub(super) category: FileCategory,
+}
+
+/// 单个目录（不含子目录）内文件的大小汇总。
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct DirRecord {
+    pub(super) mtime: u64,
+    pub(super) entry_count: u32,
+    /// 统计时目录内文件最新的 mtime（纳秒）与逻辑大小之和（含硬链接文件），见 [`DirStamp`]。
+    pub(super) newest_file_mtime: u64,
+    pub(super) file_bytes: u64,
+    pub(super) apparent: u64,
+    pub(super) allocated: u64,
+    /// 非硬链接文件按扩展名分类的逻辑大小。
+    pub(super) categories: CategorySizes,
+    /// 目录自身及其文件（含硬链接）最近一次修改或访问的时间，Unix 秒。
+    pub(super) last_activity: u64,
+    #[serde(default)]
+    pub(super) links: Vec<LinkedFile>,
+    /// 目录内无法读取的子项，复用记录时一并报告。
+    #[serde(default)]
+    pub(super) skipped: Vec<SkippedEntry>,
+    pub(super) checked_at: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct SkippedEntry {
+    pub(super) name: String,
+    pub(super) reason: SkipReason,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SizeIndexFile {
+    version: u32,
+    updated_at: u64,
+    dirs: HashMap<String, DirRecord>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SizeIndexFileRef<'a> {
+    version: u32,
+    updated_at: u64,
+    dirs: &'a HashMap<String, DirRecord>,
+}
+
+/// 跨会话的目录大小索引：上次的记录用于校验复用，本次访问到的目录写入 `fresh`。
+#[derive(Default)]
+pub(super) struct SizeIndex {
+    persistent: bool,
+    old: HashMap<String, DirRecord>,
+    fresh: HashMap<String, DirRecord>,
+    roots: HashSet<String>,
+}
+
+impl SizeIndex {
+    pub(super) fn load() -> SizeIndex {
+        let old = read_json::<SizeIndexFile>(SIZE_INDEX_FILE)
+            .filter(|f| f.version == SIZE_INDEX_VERSION)
+            .map(|f| f.dirs)
+            .unwrap_or_default();
+        SizeIndex {
+            persistent: true,
+            old,
+            ..SizeIndex::default()
+        }
+    }
+
+    /// 目录与文件的元数据都未变化且记录未过期时复用，`last_activity` 取本次读到的时间。
+    pub(super) fn lookup(&self, dir: &str, stamp: &DirStamp) -> Option<DirRecord> {
+        if stamp.mtime == 0 {
+            return None;
+        }
+        let now = now_secs();
+        let record = self.old.get(dir).filter(|r| {
+            r.mtime == stamp.mtime
+                && r.entry_count == stamp.entry_count
+                && r.newest_file_mtime == stamp.newest_file_mtime
+                && r.file_bytes == stamp.file_bytes
+                && now.saturating_sub(r.checked_at) < MAX_RECORD_AGE_SECS
+        })?;
+        let mut record = record.clone();
+        record.last_activity = record.last_activity.max(stamp.last_activity);
+        Some(record)
+    }
+
+    /// 上次保存的全部记录，不做有效性校验，仅供快速估算。
+    pub(super) fn saved_records(&self) -> impl Iterator<Item = (&String, &DirRecord)> {
+        self.old.iter()
+    }
+
+    /// 上次保存的全部目录项数，用于估计本次遍历的剩余时间。
+    pub(super) fn saved_entry_count(&self) -> u64 {
+        self.old.values().map(|r| u64::from(r.entry_count)).sum()
+    }
+
+    pub(super) fn record(&mut self, dir: String, record: DirRecord) {
+        self.fresh.insert(dir, record);
+    }
+
+    pub(super) fn mark_root(&mut self, root: &Path) {
+        self.roots.insert(root.to_string_lossy().to_string());
+    }
+
+    /// 文件系统变化涉及的目录不再复用旧记录。
+    pub(super) fn invalidate(&mut self, dir: &Path) {
+        let key = dir.to_string_lossy();
+        self.old.remove(key.as_ref());
+        self.fresh.remove(key.as_ref());
+    }
+
+    /// `dir` 及其下所有目录都不再复用旧记录。
+    pub(super) fn invalidate_subtree(&mut self, dir: &Path) {
+        self.old.retain(|d, _| !Path::new(d).starts_with(dir));
+        self.fresh.retain(|d, _| !Path::new(d).starts_with(dir));
+    }
+
+    /// 把本次访问到的记录并入索引并写盘；统计过的根目录下、但已不存在的旧记录会被丢弃。
+    pub(super) fn save(&mut self) -> Result<(), String> {
+        let fresh = std::mem::take(&mut self.fresh);
+        let roots = std::mem::take(&mut self.roots);
+        self.old.retain(|dir, _| {
+            fresh.contains_key(dir)
+                || !Path::new(dir)
+                    .ancestors()
+                    .any(|a| roots.contains(a.to_string_lossy().as_ref()))
+        });
+        self.old.extend(fresh);
+
+        if !self.persistent {
+            return Ok(());
+        }
+        write_json(
+            SIZE_INDEX_FILE,
+            &SizeIndexFileRef {
+                version: SIZE_INDEX_VERSION,
+                updated_at: now_secs(),
+                dirs: &self.old,
+            },
+        )
+    }
+}
+
+pub(super) fn size_index_info() -> SizeIndexInfo {
+    let path = file_path(SIZE_INDEX_FILE);
+    let index_bytes = path
+        .as_ref()
+        .and_then(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let file = read_json::<SizeIndexFile>(SIZE_INDEX_FILE);
+
+    SizeIndexInfo {
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        directory_count: file.as_ref().map(|f| f.dirs.len() as u32).unwrap_or(0),
+        index_bytes,
+        updated_at: file.map(|f| f.updated_at),
+    }
+}
+
+pub(super) fn clear_size_index() -> Result<(), String> {
+    remove_file(SIZE_INDEX_FILE)
+}
+
+pub(super) fn mtime_nanos(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// 修改时间与访问时间中较新的一个，Unix 秒。
+/// NTFS 默认可能关闭访问时间更新，此时只反映修改时间。
+pub(super) fn activity_secs(meta: &std::fs::Metadata) -> u64 {
+    [meta.modified(), meta.accessed()]
+        .into_iter()
+        .filter_map(|t| t.ok()?.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .max()
+        .unwrap_or(0)
+}
+
+pub(super) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(mtime: u64, entry_count: u32, checked_at: u64) -> DirRecord {
+        DirRecord {
+            mtime,
+            entry_count,
+            newest_file_mtime: 5,
+            file_bytes: 100,
+            apparent: 100,
+            allocated: 4096,
+            categories: CategorySizes::default(),
+            last_activity: 0,
+            links: Vec::new(),
+            skipped: Vec::new(),
+            checked_at,
+        }
+    }
+
+    fn stamp(mtime: u64, entry_count: u32) -> DirStamp {
+        DirStamp {
+            mtime,
+            entry_count,
+            newest_file_mtime: 5,
+            file_bytes: 100,
+            last_activity: 0,
+        }
+    }
+
+    fn saved(dirs: &[(&str, DirRecord)]) -> SizeIndex {
+        let mut index = SizeIndex::default();
+        for (dir, r) in dirs {
+            index.record(dir.to_string(), r.clone());
+        }
+        index.save().unwrap();
+        index
+    }
+
+    #[test]
+    fn lookup_requires_same_metadata_and_a_recent_check() {
+        let now = now_secs();
+        let index = saved(&[
+            ("/data/a", record(10, 3, now)),
+            ("/data/old", record(10, 3, now - MAX_RECORD_AGE_SECS)),
+            ("/data/zero", record(0, 3, now)),
+        ]);
+
+        assert!(index.lookup("/data/a", &stamp(10, 3)).is_some());
+        assert!(index.lookup("/data/a", &stamp(11, 3)).is_none());
+        assert!(index.lookup("/data/a", &stamp(10, 4)).is_none());
+        assert!(index.lookup("/data/old", &stamp(10, 3)).is_none());
+        assert!(index.lookup("/data/zero", &stamp(0, 3)).is_none());
+        assert!(index.lookup("/data/missing", &stamp(10, 3)).is_none());
+    }
+
+    #[test]
+    fn lookup_rejects_files_rewritten_in_place_and_refreshes_activity() {
+        let index = saved(&[("/data/a", record(10, 3, now_secs()))]);
+
+        let grown = DirStamp {
+            file_bytes: 180,
+            ..stamp(10, 3)
+        };
+        let touched = DirStamp {
+            newest_file_mtime: 6,
+            ..stamp(10, 3)
+        };
+        assert!(index.lookup("/data/a", &grown).is_none());
+        assert!(index.lookup("/data/a", &touched).is_none());
+
+        let read = DirStamp {
+            last_activity: 1_700_000_000,
+            ..stamp(10, 3)
+        };
+        let reused = index.lookup("/data/a", &read).unwrap();
+        assert_eq!(reused.last_activity, 1_700_000_000);
+    }
+
+    #[test]
+    fn invalidate_drops_saved_and_fresh_records() {
+        let now = now_secs();
+        let mut index = saved(&[("/data/a", record(10, 3, now))]);
+        index.record("/data/a".to_string(), record(12, 4, now));
+
+        index.invalidate(Path::new("/data/a"));
+        index.save().unwrap();
+
+        assert!(index.lookup("/data/a", &stamp(10, 3)).is_none());
+        assert!(index.lookup("/data/a", &stamp(12, 4)).is_none());
+    }
+
+    #[test]
+    fn invalidate_subtree_drops_every_record_below() {
+        let now = now_secs();
+        let mut index = saved(&[
+            ("/data/a", record(10, 1, now)),
+            ("/data/a/deep/er", record(10, 1, now)),
+            ("/data/ab", record(10, 1, now)),
+        ]);
+
+        index.invalidate_subtree(Path::new("/data/a"));
+
+        assert!(index.lookup("/data/a", &stamp(10, 1)).is_none());
+        assert!(index.lookup("/data/a/deep/er", &stamp(10, 1)).is_none());
+        assert!(index.lookup("/data/ab", &stamp(10, 1)).is_some());
+    }
+
+    #[test]
+    fn save_forgets_unvisited_dirs_only_under_walked_roots() {
+        let now = now_secs();
+        let mut index = saved(&[
+            ("/root/kept", record(10, 1, now)),
+            ("/root/gone", record(10, 1, now)),
+            ("/other/dir", record(10, 1, now)),
+        ]);
+        index.mark_root(Path::new("/root"));
+        index.record("/root/kept".to_string(), record(10, 1, now));
+        index.save().unwrap();
+
+        assert!(index.lookup("/root/kept", &stamp(10, 1)).is_some());
+        assert!(index.lookup("/root/gone", &stamp(10, 1)).is_none());
+        assert!(index.lookup("/other/dir", &stamp(10, 1)).is_some());
+        assert_eq!(index.saved_entry_count(), 2);
+    }
+}
@@ -1,16 +1,363 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
 
+use jwalk::{Parallelism, WalkDir};
+use xxhash_rust::xxh3::xxh3_64;
+
+use super::file_categories::{category_of_dir, category_of_file, CategorySizes};
+use super::size_index::{
+    activity_secs, mtime_nanos, now_secs, DirRecord, DirStamp, LinkedFile, SizeIndex, SkippedEntry,
+};
 use super::uninstall::{get_install_dir_hint, UninstallEntry};
+use crate::apps::disk_usage::file_usage;
+use crate::apps::throttle::{wait_while_paused, Throttle};
+use crate::apps::{BackgroundOptions, CancelToken, LinkPolicy, RelocatedFolder};
+
+/// 目录的逻辑大小与实际分配大小，以及逻辑大小按文件用途的划分。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct DirSize {
+    pub(super) apparent: u64,
+    pub(super) allocated: u64,
+    pub(super) categories: CategorySizes,
+    /// 子树内最近一次修改或访问的时间，Unix 秒；0 表示未知。
+    pub(super) last_activity: u64,
+    /// 抽样估算时逻辑大小的方差（字节²）；精确统计为 0。独立估算相加时方差直接相加。
+    pub(super) variance: u128,
+    /// 子树内无法读取的文件与目录数；不为 0 时大小偏小。
+    pub(super) skipped: u32,
+}
+
+impl DirSize {
+    pub(super) fn add(self, other: DirSize) -> DirSize {
+        DirSize {
+            apparent: self.apparent.saturating_add(other.apparent),
+            allocated: self.allocated.saturating_add(other.allocated),
+            categories: self.categories.merge(other.categories),
+            last_activity: self.last_activity.max(other.last_activity),
+            variance: self.variance.saturating_add(other.variance),
+            skipped: self.skipped.saturating_add(other.skipped),
+        }
+    }
+
+    /// 约 95% 置信度的误差范围（两倍标准差）。
+    pub(super) fn error_bound(&self) -> u64 {
+        2 * (self.variance as f64).sqrt() as u64
+    }
+
+    /// 按 `num / den` 放大抽样得到的部分，用于由样本推算整体。
+    fn scale(self, num: u64, den: u64) -> DirSize {
+        let f = |b: u64| (u128::from(b) * u128::from(num) / u128::from(den.max(1))) as u64;
+        DirSize {
+            apparent: f(self.apparent),
+            allocated: f(self.allocated),
+            categories: self.categories.map(f),
+            last_activity: self.last_activity,
+            variance: self.variance,
+            skipped: self.skipped,
+        }
+    }
+
+    fn of_record(record: &DirRecord) -> DirSize {
+        DirSize {
+            apparent: record.apparent,
+            allocated: record.allocated,
+            categories: record.categories,
+            last_activity: record.last_activity,
+            variance: 0,
+            skipped: record.skipped.len() as u32,
+        }
+    }
+
+    fn of_link(link: &LinkedFile) -> DirSize {
+        let mut categories = CategorySizes::default();
+        categories.add(link.category, link.apparent);
+        DirSize {
+            apparent: link.apparent,
+            allocated: link.allocated,
+            categories,
+            // 硬链接的时间已计入所在目录的记录。
+            last_activity: 0,
+            variance: 0,
+            skipped: 0,
+        }
+    }
+}
+
+/// 文件或目录无法读取的原因。
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(super) enum SkipReason {
+    PermissionDenied,
+    /// 列出目录后、读取信息前已被删除。
+    Vanished,
+    PathTooLong,
+    Other,
+}
+
+/// Windows 的 ERROR_FILENAME_EXCED_RANGE。
+const ERROR_FILENAME_EXCED_RANGE: i32 = 206;
+
+impl SkipReason {
+    fn of_io(e: &std::io::Error) -> SkipReason {
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => SkipReason::PermissionDenied,
+            std::io::ErrorKind::NotFound => SkipReason::Vanished,
+            _ if e.raw_os_error() == Some(ERROR_FILENAME_EXCED_RANGE) => SkipReason::PathTooLong,
+            _ => SkipReason::Other,
+        }
+    }
+
+    fn of_walk(e: &jwalk::Error) -> SkipReason {
+        e.io_error()
+            .map(SkipReason::of_io)
+            .unwrap_or(SkipReason::Other)
+    }
 
-pub(super) fn sum_paths(paths: &[PathBuf], size_cache: &mut HashMap<PathBuf, u64>) -> (u64, Vec<String>) {
+    pub(super) fn kind(self) -> &'static str {
+        match self {
+            SkipReason::PermissionDenied => "permissionDenied",
+            SkipReason::Vanished => "vanished",
+            SkipReason::PathTooLong => "pathTooLong",
+            SkipReason::Other => "other",
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            SkipReason::PermissionDenied => "没有访问权限",
+            SkipReason::Vanished => "统计期间已被删除",
+            SkipReason::PathTooLong => "路径过长",
+            SkipReason::Other => "读取失败",
+        }
+    }
+}
+
+/// 统计方式：快速估算给出临时结果，精确统计给出最终结果；
+/// 抽样统计代替精确统计用于用户选定的超大目录，结果附带误差范围。
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum SizePass {
+    Estimate,
+    Exact,
+    Sampled,
+}
+
+/// 一次扫描内共享的大小缓存：每个根目录只完整遍历一次，得到其下所有目录的子树大小，
+/// 之后任意文件夹的大小都直接从中查出。各部分分别加锁，可由多个统计线程同时使用。
+/// 硬链接按 (设备, 文件 id) 去重，只计入第一个遇到它的目录。
+#[derive(Default)]
+pub(super) struct SizeCache {
+    tree: Mutex<HashMap<PathBuf, DirSize>>,
+    walk_roots: Vec<WalkRoot>,
+    link_owners: Mutex<HashMap<(u64, u64), PathBuf>>,
+    index: Arc<Mutex<SizeIndex>>,
+    /// 由上次保存的索引聚合出的子树大小，快速估算时首次使用才构建。
+    estimates: OnceLock<HashMap<PathBuf, DirSize>>,
+    /// 抽样统计的结果，按文件夹缓存。
+    sampled: Mutex<HashMap<PathBuf, DirSize>>,
+    /// 精确统计与抽样统计中无法读取的路径。
+    skipped: Mutex<HashMap<PathBuf, SkipReason>>,
+    link_policy: LinkPolicy,
+    /// 遍历中遇到的指向目录的链接（符号链接、目录联接、卷挂载点）及其目标。
+    links: Mutex<HashMap<PathBuf, LinkTarget>>,
+    cancel: CancelToken,
+    /// 后台模式的限速；设置时根目录也改为单线程遍历。
+    throttle: Option<Arc<Throttle>>,
+    walk_stats: Arc<WalkStats>,
+    /// 上次保存的索引中的目录项总数；没有索引时为 0。
+    expected_entries: u64,
+    /// 有目录遍历因取消而中途返回。
+    interrupted: AtomicBool,
+}
+
+struct LinkTarget {
+    target: PathBuf,
+    volume: String,
+    /// 跟随时统计到的目标逻辑大小，不计入链接所在文件夹的大小。
+    bytes: Option<u64>,
+}
+
+thread_local! {
+    /// 本线程调用链上正在统计的文件夹与正在跟随的目标；链接绕回其中时不再进入，避免无限递归。
+    /// 只看本线程，是否跟随与其他统计线程的进度无关，结果是确定的。
+    static MEASURING: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 精确统计与抽样统计读取过的目录项与字节数，由调用线程定期读取并汇报。
+#[derive(Default)]
+pub(super) struct WalkStats {
+    files: AtomicU64,
+    bytes: AtomicU64,
+    current: Mutex<PathBuf>,
+}
+
+impl WalkStats {
+    fn record(&self, dir: &Path, files: usize, bytes: u64) {
+        self.files.fetch_add(files as u64, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        dir.clone_into(&mut lock(&self.current));
+    }
+
+    /// (目录项数, 字节数, 最近处理的目录)。
+    pub(super) fn snapshot(&self) -> (u64, u64, PathBuf) {
+        (
+            self.files.load(Ordering::Relaxed),
+            self.bytes.load(Ordering::Relaxed),
+            lock(&self.current).clone(),
+        )
+    }
+}
+
+/// 整体遍历一次的根目录（LOCALAPPDATA、APPDATA 等），其下的文件夹不再单独遍历。
+struct WalkRoot {
+    path: PathBuf,
+    walked: OnceLock<()>,
+}
+
+impl SizeCache {
+    /// 加载磁盘上的大小索引，未变化的目录无需再逐个读取文件信息。
+    pub(super) fn with_persistent_index(
+        cancel: CancelToken,
+        walk_roots: Vec<PathBuf>,
+    ) -> SizeCache {
+        let index = SizeIndex::load();
+        SizeCache {
+            expected_entries: index.saved_entry_count(),
+            walk_roots: walk_roots
+                .into_iter()
+                .map(|path| WalkRoot {
+                    path,
+                    walked: OnceLock::new(),
+                })
+                .collect(),
+            index: Arc::new(Mutex::new(index)),
+            cancel,
+            ..SizeCache::default()
+        }
+    }
+
+    /// 遇到符号链接、目录联接与卷挂载点时的处理方式，默认不跟随。
+    pub(super) fn with_link_policy(mut self, policy: LinkPolicy) -> SizeCache {
+        self.link_policy = policy;
+        self
+    }
+
+    pub(super) fn with_background(mut self, options: Option<&BackgroundOptions>) -> SizeCache {
+        self.throttle = options.map(|o| Arc::new(Throttle::new(o)));
+        self
+    }
+
+    pub(super) fn walk_stats(&self) -> &WalkStats {
+        &self.walk_stats
+    }
+
+    pub(super) fn expected_entries(&self) -> u64 {
+        self.expected_entries
+    }
+
+    fn pace(&self, files: usize, bytes: u64) {
+        pace(self.throttle.as_deref(), &self.cancel, files, bytes);
+    }
+
+    /// 被取消的遍历只访问了部分子树，写入索引会误删其余记录，因此直接跳过。
+    pub(super) fn save_index(&self) -> Result<(), String> {
+        if self.interrupted.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        lock(&self.index).save()
+    }
+
+    /// `touched` 发生变化后，包含它的目录的子树大小需要重新计算；
+    /// 只有它自身及其父目录的索引记录失效，其余子目录仍按 mtime 复用。
+    pub(super) fn invalidate(&self, touched: &Path) {
+        lock(&self.tree).retain(|dir, _| !touched.starts_with(dir));
+        lock(&self.sampled).retain(|dir, _| !touched.starts_with(dir));
+        lock(&self.skipped).retain(|path, _| !path.starts_with(touched));
+        lock(&self.links).retain(|path, _| !path.starts_with(touched));
+        let mut index = lock(&self.index);
+        index.invalidate(touched);
+        if let Some(parent) = touched.parent() {
+            index.invalidate(parent);
+        }
+    }
+
+    /// 不知道 `dir` 下具体哪些目录变化时使用：其下所有目录的子树大小与索引记录都失效，
+    /// 原地改写的深层目录不会因 mtime 未变而被复用。
+    pub(super) fn invalidate_subtree(&self, dir: &Path) {
+        self.invalidate(dir);
+        lock(&self.tree).retain(|d, _| !d.starts_with(dir));
+        lock(&self.sampled).retain(|d, _| !d.starts_with(dir));
+        lock(&self.index).invalidate_subtree(dir);
+    }
+
+    /// 本次扫描中无法读取的路径，按路径排序。
+    pub(super) fn skipped_paths(&self) -> Vec<(PathBuf, SkipReason)> {
+        let mut out: Vec<(PathBuf, SkipReason)> = lock(&self.skipped)
+            .iter()
+            .map(|(p, r)| (p.clone(), *r))
+            .collect();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    fn record_skipped(&self, path: PathBuf, reason: SkipReason) {
+        lock(&self.skipped).insert(path, reason);
+    }
+
+    /// `paths` 中各文件夹（含自身）之下指向别处的文件夹，按路径排序。
+    pub(super) fn relocated_under(&self, paths: &[PathBuf]) -> Vec<RelocatedFolder> {
+        self.relocated(|link| paths.iter().any(|p| link.starts_with(p)))
+    }
+
+    /// 本次扫描中遇到的全部指向目录的链接，按路径排序。
+    pub(super) fn relocated_folders(&self) -> Vec<RelocatedFolder> {
+        self.relocated(|_| true)
+    }
+
+    fn relocated(&self, keep: impl Fn(&Path) -> bool) -> Vec<RelocatedFolder> {
+        let mut out: Vec<RelocatedFolder> = lock(&self.links)
+            .iter()
+            .filter(|(link, _)| keep(link))
+            .map(|(link, t)| RelocatedFolder {
+                path: link.to_string_lossy().to_string(),
+                target: t.target.to_string_lossy().to_string(),
+                volume: t.volume.clone(),
+                bytes: t.bytes,
+            })
+            .collect();
+        out.sort_by(|a, b| a.path.cmp(&b.path));
+        out
+    }
+}
+
+/// 每处理完一个目录调用：暂停时等待，后台模式下按限速睡眠。
+fn pace(throttle: Option<&Throttle>, cancel: &CancelToken, files: usize, bytes: u64) {
+    match throttle {
+        Some(t) => t.pace(files as u64, bytes, cancel),
+        None => wait_while_paused(cancel),
+    }
+}
+
+/// 某个统计线程 panic 后缓存内容仍然可用，不让其余线程跟着失败。
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(super) fn sum_paths(
+    paths: &[PathBuf],
+    size_cache: &SizeCache,
+    pass: SizePass,
+) -> (DirSize, Vec<String>) {
     let mut items: Vec<(u64, String)> = Vec::new();
-    let mut total = 0u64;
+    let mut total = DirSize::default();
 
     for p in paths {
-        let bytes = directory_size_cached(p, size_cache);
-        total = total.saturating_add(bytes);
-        items.push((bytes, p.to_string_lossy().to_string()));
+        let size = folder_size(p, size_cache, pass);
+        total = total.add(size);
+        items.push((size.apparent, p.to_string_lossy().to_string()));
     }
 
     items.sort_by(|a, b| b.0.cmp(&a.0));
@@ -20,41 +367,703 @@ pub(super) fn sum_paths(paths: &[PathBuf], size_cache: &mut HashMap<PathBuf, u64
 
 pub(super) fn compute_install_bytes(
     uninstall: &UninstallEntry,
-    size_cache: &mut HashMap<PathBuf, u64>,
-) -> (u64, Vec<String>) {
+    size_cache: &SizeCache,
+    pass: SizePass,
+) -> (DirSize, Vec<String>) {
     let Some(dir) = get_install_dir_hint(uninstall) else {
-        return (0, Vec::new());
+        return (DirSize::default(), Vec::new());
     };
 
-    let bytes = directory_size_cached(&dir, size_cache);
+    let size = folder_size(&dir, size_cache, pass);
     let paths = vec![dir.to_string_lossy().to_string()];
-    (bytes, paths)
+    (size, paths)
+}
+
+/// 只读取目录第一层的时间：注册表已给出大小、不遍历安装目录时，
+/// 主程序通常就在第一层，启动时会刷新其访问时间。
+pub(super) fn shallow_activity(dir: &Path) -> u64 {
+    let own = std::fs::metadata(dir)
+        .map(|m| mtime_nanos(&m) / 1_000_000_000)
+        .unwrap_or(0);
+    let Ok(rd) = std::fs::read_dir(dir) else {
+        return own;
+    };
+    rd.flatten()
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| activity_secs(&m))
+        .fold(own, u64::max)
 }
 
-pub(super) fn directory_size(root: &Path) -> u64 {
-    use jwalk::WalkDir;
+/// 根目录遍历的线程数；目录统计以磁盘 I/O 为主，线程过多反而互相抢占。
+const ROOT_WALK_THREADS: usize = 4;
+
+/// 快速估算时单个文件夹最多读取的目录项数；超出后按已访问目录的比例推算整体。
+const ESTIMATE_ENTRY_LIMIT: usize = 2_000;
 
-    let mut total = 0u64;
-    for entry in WalkDir::new(root).into_iter().flatten() {
-        if !entry.file_type().is_file() {
+type WalkEntry = jwalk::DirEntry<((), ())>;
+
+fn folder_size(path: &Path, cache: &SizeCache, pass: SizePass) -> DirSize {
+    match pass {
+        SizePass::Exact => directory_size_cached(path, cache),
+        SizePass::Estimate => estimate_size(path, cache),
+        SizePass::Sampled => sampled_size(path, cache),
+    }
+}
+
+/// 依次尝试本次已精确统计的结果、上次保存的索引与有限遍历。
+fn estimate_size(path: &Path, cache: &SizeCache) -> DirSize {
+    if let Some(v) = lock(&cache.tree).get(path) {
+        return *v;
+    }
+    let estimates = cache.estimates.get_or_init(|| {
+        let index = lock(&cache.index);
+        let mut sizes: HashMap<PathBuf, DirSize> = index
+            .saved_records()
+            .map(|(dir, r)| {
+                let own = r
+                    .links
+                    .iter()
+                    .fold(DirSize::of_record(r), |acc, l| acc.add(DirSize::of_link(l)));
+                (PathBuf::from(dir), own)
+            })
+            .collect();
+        aggregate_subtrees(&mut sizes);
+        sizes
+    });
+    if let Some(v) = estimates.get(path) {
+        return *v;
+    }
+    bounded_walk(path, cache)
+}
+
+/// 读到 [`ESTIMATE_ENTRY_LIMIT`] 个目录项后停止，以已访问目录的平均大小推算尚未访问的目录：
+/// 结果乘以（已访问 + 待访问）/ 已访问的目录数，读到一半的目录算作已访问。
+/// 只是粗略估计，精确统计完成后会被替换。
+fn bounded_walk(root: &Path, cache: &SizeCache) -> DirSize {
+    let mut pending = vec![(root.to_path_buf(), None)];
+    let mut seen = 0usize;
+    let mut visited = 0u64;
+    let mut total = DirSize::default();
+    while let Some((dir, dir_category)) = pending.pop() {
+        let Ok(rd) = std::fs::read_dir(&dir) else {
             continue;
+        };
+        visited += 1;
+        let before = (seen, total.apparent);
+        for e in rd.flatten() {
+            seen += 1;
+            if seen > ESTIMATE_ENTRY_LIMIT {
+                return total.scale(visited + pending.len() as u64, visited);
+            }
+            let Ok(t) = e.file_type() else {
+                continue;
+            };
+            let path = e.path();
+            if t.is_dir() {
+                let category = dir_category.or_else(|| category_of_dir(&path));
+                pending.push((path, category));
+            } else if t.is_file() {
+                if let Ok(m) = e.metadata() {
+                    let mut categories = CategorySizes::default();
+                    categories.add(
+                        dir_category.unwrap_or_else(|| category_of_file(&path)),
+                        m.len(),
+                    );
+                    total = total.add(DirSize {
+                        apparent: m.len(),
+                        allocated: m.len(),
+                        categories,
+                        last_activity: activity_secs(&m),
+                        variance: 0,
+                        skipped: 0,
+                    });
+                }
+            }
         }
-        let size = entry
-            .metadata()
-            .map(|m| m.len())
-            .or_else(|_| std::fs::metadata(entry.path()).map(|m| m.len()))
-            .unwrap_or(0);
-        total = total.saturating_add(size);
+        cache.pace(seen - before.0, total.apparent - before.1);
     }
     total
 }
 
-fn directory_size_cached(root: &Path, cache: &mut HashMap<PathBuf, u64>) -> u64 {
-    let key = root.to_path_buf();
-    if let Some(v) = cache.get(&key) {
+/// 精确统计的层数：根目录及其下这么多层内的每个子目录都会访问。
+const SAMPLE_EXACT_DEPTH: u32 = 2;
+
+/// 更深处子目录多于此数时，只抽取这么多个访问，其余按样本均值推算。
+const SAMPLE_CHILDREN: usize = 8;
+
+/// 已精确统计过的文件夹直接取精确值，否则抽样统计。
+fn sampled_size(path: &Path, cache: &SizeCache) -> DirSize {
+    if let Some(v) = lock(&cache.tree).get(path) {
         return *v;
     }
-    let bytes = directory_size(root);
-    cache.insert(key, bytes);
-    bytes
+    if let Some(v) = lock(&cache.sampled).get(path) {
+        return *v;
+    }
+    let size = measuring(path, || sample_dir(path, 0, cache));
+    if !cache.cancel.is_cancelled() {
+        lock(&cache.sampled).insert(path.to_path_buf(), size);
+    }
+    size
+}
+
+/// 访问到的目录内文件逐个精确统计；深层目录的子目录过多时做两阶段抽样：
+/// 按名称哈希确定性地抽取子目录，以样本均值乘以子目录总数推算，
+/// 方差为抽样间方差（含有限总体校正）加上各样本自身方差的放大。
+/// 硬链接不去重，可能略微偏大；未抽中的子树中无法读取的路径不会被发现。
+fn sample_dir(dir: &Path, depth: u32, cache: &SizeCache) -> DirSize {
+    let mut own = DirSize::default();
+    let skip = |path: PathBuf, err: &std::io::Error, own: &mut DirSize| {
+        let reason = SkipReason::of_io(err);
+        // 根目录本身不存在时大小为 0，不算跳过。
+        if depth == 0 && reason == SkipReason::Vanished {
+            return;
+        }
+        own.skipped = own.skipped.saturating_add(1);
+        cache.record_skipped(path, reason);
+    };
+    let rd = match std::fs::read_dir(dir) {
+        Ok(rd) => rd,
+        Err(err) => {
+            skip(dir.to_path_buf(), &err, &mut own);
+            return own;
+        }
+    };
+    let mut subdirs = Vec::new();
+    let mut entries = 0usize;
+    for e in rd {
+        if cache.cancel.is_cancelled() {
+            return DirSize::default();
+        }
+        entries += 1;
+        let e = match e {
+            Ok(e) => e,
+            Err(err) => {
+                skip(dir.to_path_buf(), &err, &mut own);
+                continue;
+            }
+        };
+        let path = e.path();
+        let t = match e.file_type() {
+            Ok(t) => t,
+            Err(err) => {
+                skip(path, &err, &mut own);
+                continue;
+            }
+        };
+        if t.is_dir() {
+            subdirs.push(path);
+        } else if t.is_symlink() {
+            own = own.add(link_size(&path, cache, SizePass::Sampled));
+        } else if t.is_file() {
+            let meta = match e.metadata() {
+                Ok(meta) => meta,
+                Err(err) => {
+                    skip(path, &err, &mut own);
+                    continue;
+                }
+            };
+            let usage = file_usage(&path, &meta);
+            let mut categories = CategorySizes::default();
+            categories.add(category_of_file(&path), usage.apparent);
+            own = own.add(DirSize {
+                apparent: usage.apparent,
+                allocated: usage.allocated,
+                categories,
+                last_activity: activity_secs(&meta),
+                variance: 0,
+                skipped: 0,
+            });
+        }
+    }
+
+    cache.walk_stats.record(dir, entries, own.apparent);
+    cache.pace(entries, own.apparent);
+
+    if depth < SAMPLE_EXACT_DEPTH || subdirs.len() <= SAMPLE_CHILDREN {
+        for s in &subdirs {
+            own = own.add(as_child(s, sample_dir(s, depth + 1, cache)));
+        }
+    } else {
+        subdirs.sort_by_key(|p| xxh3_64(p.as_os_str().as_encoded_bytes()));
+        let samples: Vec<DirSize> = subdirs[..SAMPLE_CHILDREN]
+            .iter()
+            .map(|s| as_child(s, sample_dir(s, depth + 1, cache)))
+            .collect();
+        let total = subdirs.len() as f64;
+        let n = SAMPLE_CHILDREN as f64;
+        let mean = samples.iter().map(|x| x.apparent as f64).sum::<f64>() / n;
+        let s2 = samples
+            .iter()
+            .map(|x| (x.apparent as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0);
+        let between = total * total * (1.0 - n / total) * s2 / n;
+        let within = total / n * samples.iter().map(|x| x.variance as f64).sum::<f64>();
+        let sum = samples
+            .iter()
+            .fold(DirSize::default(), |acc, x| acc.add(*x));
+        let mut estimate = sum.scale(subdirs.len() as u64, SAMPLE_CHILDREN as u64);
+        estimate.variance = (between + within) as u128;
+        own = own.add(estimate);
+    }
+    own
+}
+
+/// 子目录并入上层时，目录名表明用途（如 `Cache`、`logs`）的整个子树归入该类别。
+fn as_child(dir: &Path, mut size: DirSize) -> DirSize {
+    if let Some(category) = category_of_dir(dir) {
+        size.categories = size.categories.collapse_into(category);
+    }
+    size
+}
+
+/// 只查内存中的子树大小表，不触发遍历；尚未统计或已失效时返回 `None`。
+pub(super) fn known_directory_size(path: &Path, cache: &SizeCache) -> Option<DirSize> {
+    lock(&cache.tree).get(path).copied()
+}
+
+pub(super) fn directory_size_cached(path: &Path, cache: &SizeCache) -> DirSize {
+    if let Some(v) = lock(&cache.tree).get(path) {
+        return *v;
+    }
+    if let Some(root) = cache.walk_roots.iter().find(|r| path.starts_with(&r.path)) {
+        // 其他线程同时请求同一根目录时在此等待，而不是重复遍历。
+        root.walked.get_or_init(|| {
+            walk_tree(&root.path, cache, cache.throttle.is_none());
+        });
+        if let Some(v) = lock(&cache.tree).get(path) {
+            return *v;
+        }
+    }
+    // 不在任何根目录下，或自遍历后已失效，单独遍历该子树。
+    measuring(path, || walk_tree(path, cache, false))
+}
+
+/// 遍历 `root` 下的所有目录：逐目录校验索引，mtime 与子项数量一致时复用该目录下文件的大小汇总，
+/// 否则重新统计；随后由深到浅把各目录汇总并入父目录，得到每个目录的子树大小并写入缓存。
+fn walk_tree(root: &Path, cache: &SizeCache, parallel: bool) -> DirSize {
+    // jwalk 不会进入本身就是链接的根目录，按链接策略单独处理。
+    if std::fs::symlink_metadata(root).is_ok_and(|m| m.file_type().is_symlink()) {
+        let size = link_size(root, cache, SizePass::Exact);
+        if !cache.cancel.is_cancelled() {
+            lock(&cache.tree).insert(root.to_path_buf(), size);
+        }
+        return size;
+    }
+
+    let records: Arc<Mutex<Vec<(PathBuf, DirRecord)>>> = Arc::default();
+    let links: Arc<Mutex<Vec<PathBuf>>> = Arc::default();
+    let walk = {
+        let records = Arc::clone(&records);
+        let links = Arc::clone(&links);
+        let index = Arc::clone(&cache.index);
+        let cancel = cache.cancel.clone();
+        let throttle = cache.throttle.clone();
+        let stats = Arc::clone(&cache.walk_stats);
+        WalkDir::new(root)
+            .skip_hidden(false)
+            .follow_links(false)
+            .parallelism(if parallel {
+                Parallelism::RayonNewPool(ROOT_WALK_THREADS)
+            } else {
+                Parallelism::Serial
+            })
+            .process_read_dir(move |depth, dir, _, children| {
+                // depth 为 None 时是根目录自身所在的父目录，不计入。
+                if depth.is_none() {
+                    return;
+                }
+                if cancel.is_cancelled() {
+                    children.clear();
+                    return;
+                }
+                let entries: Vec<&WalkEntry> = children.iter().flatten().collect();
+                lock(&links).extend(
+                    entries
+                        .iter()
+                        .filter(|e| e.file_type().is_symlink())
+                        .map(|e| e.path()),
+                );
+                let errors: Vec<SkippedEntry> = children
+                    .iter()
+                    .filter_map(|c| c.as_ref().err())
+                    .map(|e| SkippedEntry {
+                        name: e
+                            .path()
+                            .and_then(Path::file_name)
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        reason: SkipReason::of_walk(e),
+                    })
+                    .collect();
+                let mtime = std::fs::metadata(dir).map(|m| mtime_nanos(&m)).unwrap_or(0);
+                let files: Vec<(&WalkEntry, jwalk::Result<std::fs::Metadata>)> = entries
+                    .iter()
+                    .filter(|e| e.file_type().is_file())
+                    .map(|e| (*e, e.metadata()))
+                    .collect();
+                let stamp = DirStamp::of_files(
+                    mtime,
+                    entries.len() as u32,
+                    files.iter().filter_map(|(_, m)| m.as_ref().ok()),
+                );
+                let key = dir.to_string_lossy().to_string();
+                let reused = lock(&index).lookup(&key, &stamp);
+                let fresh = reused.is_none();
+                let record = reused.unwrap_or_else(|| scan_dir_files(&files, errors, &stamp));
+                // 复用的记录只读取了目录列表与文件元数据，不计文件字节。
+                let bytes = if fresh { record.apparent } else { 0 };
+                stats.record(dir, entries.len(), record.apparent);
+                pace(throttle.as_deref(), &cancel, entries.len(), bytes);
+                lock(&records).push((dir.to_path_buf(), record));
+            })
+    };
+    // 无法列出子项的目录没有记录，稍后以一个跳过计数代替，让它的父目录也标为不完整。
+    // 根目录本身不存在时大小为 0，不算跳过。
+    let mut unreadable: Vec<(PathBuf, SkipReason)> = Vec::new();
+    for entry in walk {
+        match entry {
+            Ok(e) => {
+                if let Some(err) = &e.read_children_error {
+                    unreadable.push((e.path(), SkipReason::of_walk(err)));
+                }
+            }
+            Err(err) if err.depth() == 0 => {
+                let reason = SkipReason::of_walk(&err);
+                if reason != SkipReason::Vanished {
+                    unreadable.push((root.to_path_buf(), reason));
+                }
+            }
+            // 其余条目错误已在所在目录的记录中统计。
+            Err(_) => {}
+        }
+    }
+
+    // 链接按策略计入，跟随时可能遍历其他卷上的目标，因此放在取消检查之前。
+    let links = std::mem::take(&mut *lock(&links));
+    let link_sizes: Vec<(PathBuf, DirSize)> = links
+        .into_iter()
+        .map(|link| {
+            let size = link_size(&link, cache, SizePass::Exact);
+            (link, size)
+        })
+        .collect();
+
+    if cache.cancel.is_cancelled() {
+        cache.interrupted.store(true, Ordering::Relaxed);
+        return DirSize::default();
+    }
+
+    // 并行遍历的完成顺序不固定，排序后硬链接总是归入路径最靠前的目录。
+    let mut records = std::mem::take(&mut *lock(&records));
+    records.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut sizes: HashMap<PathBuf, DirSize> = HashMap::with_capacity(records.len());
+    {
+        let mut index = lock(&cache.index);
+        let mut link_owners = lock(&cache.link_owners);
+        index.mark_root(root);
+        for (dir, record) in records {
+            let mut own = DirSize::of_record(&record);
+            for e in &record.skipped {
+                cache.record_skipped(dir.join(&e.name), e.reason);
+            }
+            for l in &record.links {
+                let owner = link_owners
+                    .entry((l.volume, l.file_id))
+                    .or_insert_with(|| dir.clone());
+                if *owner == dir {
+                    own = own.add(DirSize::of_link(l));
+                }
+            }
+            index.record(dir.to_string_lossy().to_string(), record);
+            sizes.insert(dir, own);
+        }
+    }
+    sizes.extend(link_sizes);
+    for (dir, reason) in unreadable {
+        let size = sizes.entry(dir.clone()).or_default();
+        size.skipped = size.skipped.saturating_add(1);
+        cache.record_skipped(dir, reason);
+    }
+
+    aggregate_subtrees(&mut sizes);
+
+    let total = sizes.get(root).copied().unwrap_or_default();
+    lock(&cache.tree).extend(sizes);
+    total
+}
+
+/// 按链接策略统计一个符号链接、目录联接或卷挂载点；指向目录时记录其目标与所在卷。
+/// 链接本身按策略计入所在文件夹；跟随时另外统计目标的大小，按目标所在的卷单独报告，
+/// 不计入所在文件夹，因此多个链接指向同一目标时不会重复计算，也不取决于谁先遇到它。
+/// 目标位于整体遍历的根目录内（已计入根目录）或绕回本线程正在统计的文件夹时不跟随。
+fn link_size(link: &Path, cache: &SizeCache, pass: SizePass) -> DirSize {
+    let target = std::fs::canonicalize(link).ok().map(plain_path);
+    let followed = match (&target, cache.link_policy) {
+        (Some(t), LinkPolicy::Follow) if t.is_dir() && may_follow(link, t, cache) => {
+            Some(measuring(t, || folder_size(t, cache, pass)))
+        }
+        _ => None,
+    };
+    if let Some(t) = target.filter(|t| t.is_dir()) {
+        let volume = volume_of(&t);
+        lock(&cache.links).insert(
+            link.to_path_buf(),
+            LinkTarget {
+                target: t,
+                volume,
+                bytes: followed.map(|s| s.apparent),
+            },
+        );
+    }
+    match cache.link_policy {
+        LinkPolicy::Skip => DirSize::default(),
+        LinkPolicy::LinkOnly | LinkPolicy::Follow => {
+            entry_size(link, std::fs::symlink_metadata(link))
+        }
+    }
+}
+
+fn may_follow(link: &Path, target: &Path, cache: &SizeCache) -> bool {
+    if link.starts_with(target) || cache.walk_roots.iter().any(|r| target.starts_with(&r.path)) {
+        return false;
+    }
+    MEASURING.with_borrow(|stack| {
+        !stack
+            .iter()
+            .any(|p| target.starts_with(p) || p.starts_with(target))
+    })
+}
+
+/// 统计期间把 `path` 压入本线程的调用链，其下的链接不会再跟随回到这里。
+fn measuring<T>(path: &Path, f: impl FnOnce() -> T) -> T {
+    MEASURING.with_borrow_mut(|stack| stack.push(path.to_path_buf()));
+    let out = f();
+    MEASURING.with_borrow_mut(|stack| stack.pop());
+    out
+}
+
+/// 单个文件或链接自身的占用。
+fn entry_size(path: &Path, meta: std::io::Result<std::fs::Metadata>) -> DirSize {
+    let Ok(meta) = meta else {
+        return DirSize::default();
+    };
+    let usage = file_usage(path, &meta);
+    let mut categories = CategorySizes::default();
+    categories.add(category_of_file(path), usage.apparent);
+    DirSize {
+        apparent: usage.apparent,
+        allocated: usage.allocated,
+        categories,
+        last_activity: activity_secs(&meta),
+        variance: 0,
+        skipped: 0,
+    }
+}
+
+/// 去掉 `canonicalize` 加上的 `\\?\` 前缀，使盘符与 UNC 路径能与其他路径比较；
+/// 卷 GUID 路径（未分配盘符的挂载点）保持原样。
+fn plain_path(path: PathBuf) -> PathBuf {
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return path;
+    };
+    let s = path.to_string_lossy();
+    match prefix.kind() {
+        Prefix::VerbatimDisk(_) => PathBuf::from(&s[4..]),
+        Prefix::VerbatimUNC(..) => PathBuf::from(format!(r"\\{}", &s[8..])),
+        _ => path.clone(),
+    }
+}
+
+/// 路径所在的卷：盘符（如 `D:`）、UNC 共享或卷 GUID。
+fn volume_of(path: &Path) -> String {
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(d) | Prefix::VerbatimDisk(d) => format!("{}:", char::from(d)),
+            Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => format!(
+                r"\\{}\{}",
+                server.to_string_lossy(),
+                share.to_string_lossy()
+            ),
+            _ => prefix.as_os_str().to_string_lossy().to_string(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// 由深到浅把每个目录自身的汇总并入父目录，得到子树大小。
+/// 目录自身的子树大小保留按文件的细分，并入父目录时才按目录名归类（见 [`as_child`]）。
+fn aggregate_subtrees(sizes: &mut HashMap<PathBuf, DirSize>) {
+    let mut dirs: Vec<PathBuf> = sizes.keys().cloned().collect();
+    dirs.sort_by_key(|d| Reverse(d.components().count()));
+    for d in &dirs {
+        let Some(size) = sizes.get(d) else {
+            continue;
+        };
+        let own = as_child(d, *size);
+        if let Some(parent) = d.parent().and_then(|p| sizes.get_mut(p)) {
+            *parent = parent.add(own);
+        }
+    }
+}
+
+fn scan_dir_files(
+    files: &[(&WalkEntry, jwalk::Result<std::fs::Metadata>)],
+    skipped: Vec<SkippedEntry>,
+    stamp: &DirStamp,
+) -> DirRecord {
+    let mut record = DirRecord {
+        mtime: stamp.mtime,
+        entry_count: stamp.entry_count,
+        newest_file_mtime: stamp.newest_file_mtime,
+        file_bytes: stamp.file_bytes,
+        apparent: 0,
+        allocated: 0,
+        categories: CategorySizes::default(),
+        // 目录自身只看修改时间：本次遍历读取目录会刷新它的访问时间。
+        last_activity: stamp.mtime / 1_000_000_000,
+        links: Vec::new(),
+        skipped,
+        checked_at: now_secs(),
+    };
+
+    for (e, meta) in files {
+        let path = e.path();
+        let meta = match meta {
+            Ok(meta) => meta,
+            Err(err) => {
+                record.skipped.push(SkippedEntry {
+                    name: e.file_name().to_string_lossy().to_string(),
+                    reason: SkipReason::of_walk(err),
+                });
+                continue;
+            }
+        };
+        let usage = file_usage(&path, meta);
+        let category = category_of_file(&path);
+        record.last_activity = record.last_activity.max(activity_secs(meta));
+        match usage.link_id {
+            Some((volume, file_id)) => record.links.push(LinkedFile {
+                volume,
+                file_id,
+                apparent: usage.apparent,
+                allocated: usage.allocated,
+                category,
+            }),
+            None => {
+                record.apparent = record.apparent.saturating_add(usage.apparent);
+                record.allocated = record.allocated.saturating_add(usage.allocated);
+                record.categories.add(category, usage.apparent);
+            }
+        }
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::windows::file_categories::FileCategory;
+
+    #[test]
+    fn bounded_walk_extrapolates_unvisited_dirs() {
+        let root = std::env::temp_dir().join(format!("appmanager-bounded-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for d in 0..30 {
+            let dir = root.join(format!("d{d:02}"));
+            std::fs::create_dir_all(&dir).unwrap();
+            for f in 0..100 {
+                std::fs::write(dir.join(format!("f{f}.bin")), [0u8; 10]).unwrap();
+            }
+        }
+
+        let size = bounded_walk(&root, &SizeCache::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // 精确值为 30 × 100 × 10 字节；只读到约三分之二，推算后应接近精确值。
+        let exact = 30_000u64;
+        assert!(size.apparent.abs_diff(exact) * 10 < exact);
+    }
+
+    #[test]
+    fn named_dir_keeps_its_own_categories_but_collapses_into_parent() {
+        let mut categories = CategorySizes::default();
+        categories.add(FileCategory::Databases, 100);
+        let own = DirSize {
+            apparent: 100,
+            allocated: 100,
+            categories,
+            ..DirSize::default()
+        };
+        let mut sizes = HashMap::from([
+            (PathBuf::from("/data/Cache"), own),
+            (PathBuf::from("/data"), DirSize::default()),
+        ]);
+
+        aggregate_subtrees(&mut sizes);
+
+        assert_eq!(sizes[Path::new("/data/Cache")].categories, categories);
+        let mut collapsed = CategorySizes::default();
+        collapsed.add(FileCategory::Cache, 100);
+        assert_eq!(sizes[Path::new("/data")].categories, collapsed);
+    }
+
+    #[test]
+    fn small_hard_links_are_counted_once() {
+        let root = std::env::temp_dir().join(format!("appmanager-links-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let (a, b) = (root.join("a"), root.join("b"));
+        std::fs::create_dir_all(&a).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+        std::fs::write(a.join("lib.dll"), [0u8; 300]).unwrap();
+        std::fs::hard_link(a.join("lib.dll"), b.join("lib.dll")).unwrap();
+        std::fs::write(b.join("own.dll"), [0u8; 50]).unwrap();
+
+        let size = directory_size_cached(&root, &SizeCache::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(size.apparent, 350);
+    }
+
+    #[test]
+    fn subtree_invalidation_remeasures_dirs_rewritten_in_place() {
+        let root = std::env::temp_dir().join(format!("appmanager-subtree-{}", std::process::id()));
+        let deep = root.join("a").join("b");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("app.log"), [0u8; 100]).unwrap();
+        let cache = SizeCache::default();
+        assert_eq!(directory_size_cached(&root, &cache).apparent, 100);
+        cache.save_index().unwrap();
+
+        // 原地改写不改变目录的 mtime 与子项数。
+        std::fs::write(deep.join("app.log"), [0u8; 700]).unwrap();
+        cache.invalidate_subtree(&root);
+        let size = directory_size_cached(&root, &cache);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(size.apparent, 700);
+    }
+
+    #[test]
+    fn index_records_are_not_reused_after_in_place_rewrites() {
+        let root = std::env::temp_dir().join(format!("appmanager-rewrite-{}", std::process::id()));
+        let deep = root.join("a").join("b");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("app.db"), [0u8; 100]).unwrap();
+        let cache = SizeCache::default();
+        assert_eq!(directory_size_cached(&root, &cache).apparent, 100);
+        cache.save_index().unwrap();
+
+        // 只让根目录失效：深层目录的 mtime 与子项数未变，但文件大小变了。
+        std::fs::write(deep.join("app.db"), [0u8; 400]).unwrap();
+        cache.invalidate(&root);
+        lock(&cache.tree).clear();
+        let size = directory_size_cached(&root, &cache);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(size.apparent, 400);
+    }
 }
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+/// 与 tauri.conf.json 中的 identifier 保持一致，和 Tauri 的本地数据目录相同。
+const APP_DIR_NAME: &str = "com.cedric.appmanager";
+
+/// 本地持久化目录：`%LOCALAPPDATA%\com.cedric.appmanager`。
+pub(super) fn app_data_dir() -> Option<PathBuf> {
+    let dir = PathBuf::from(std::env::var_os("LOCALAPPDATA")?).join(APP_DIR_NAME);
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+pub(super) fn read_json<T: serde::de::DeserializeOwned>(file_name: &str) -> Option<T> {
+    read_json_at(&app_data_dir()?.join(file_name))
+}
+
+pub(super) fn read_json_at<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub(super) fn write_json<T: serde::Serialize>(file_name: &str, value: &T) -> Result<(), String> {
+    let dir = app_data_dir().ok_or_else(|| "无法定位本地数据目录".to_string())?;
+    write_json_at(&dir.join(file_name), value)
+}
+
+/// 先写临时文件再重命名，避免写到一半崩溃留下损坏的文件。
+pub(super) fn write_json_at<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, text).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+pub(super) fn file_path(file_name: &str) -> Option<PathBuf> {
+    Some(app_data_dir()?.join(file_name))
+}
+
+pub(super) fn remove_file(file_name: &str) -> Result<(), String> {
+    let Some(path) = file_path(file_name) else {
+        return Ok(());
+    };
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
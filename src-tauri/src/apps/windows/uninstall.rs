@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::matching::normalize_key;
 
@@ -11,8 +11,12 @@ pub(super) struct UninstallEntry {
     pub(super) estimated_bytes: u64,
     pub(super) install_location: Option<String>,
     pub(super) display_icon: Option<String>,
+    pub(super) uninstall_string: Option<String>,
+    /// 完整的注册表项路径，审计报告中用于定位失效的条目；去重合并后包含每一处登记。
+    pub(super) registry_keys: Vec<String>,
 }
 
+#[cfg(windows)]
 pub(super) fn scan_uninstall_entries() -> Vec<UninstallEntry> {
     use winreg::enums::*;
     use winreg::RegKey;
@@ -26,23 +30,37 @@ pub(super) fn scan_uninstall_entries() -> Vec<UninstallEntry> {
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     out.extend(read_uninstall_key(
         &hklm,
+        "HKEY_LOCAL_MACHINE",
         UNINSTALL_PATH,
         KEY_READ | KEY_WOW64_64KEY,
         "hklm64",
     ));
     out.extend(read_uninstall_key(
         &hklm,
+        "HKEY_LOCAL_MACHINE",
         UNINSTALL_WOW6432_PATH,
         KEY_READ | KEY_WOW64_32KEY,
         "hklm32",
     ));
 
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    out.extend(read_uninstall_key(&hkcu, UNINSTALL_PATH, KEY_READ, "hkcu"));
+    out.extend(read_uninstall_key(
+        &hkcu,
+        "HKEY_CURRENT_USER",
+        UNINSTALL_PATH,
+        KEY_READ,
+        "hkcu",
+    ));
 
     out
 }
 
+/// 注册表只在 Windows 上存在；其他平台只用得到离线评估，没有卸载项可读。
+#[cfg(not(windows))]
+pub(super) fn scan_uninstall_entries() -> Vec<UninstallEntry> {
+    Vec::new()
+}
+
 pub(super) fn dedupe_uninstall_entries(entries: Vec<UninstallEntry>) -> Vec<UninstallEntry> {
     let mut map: HashMap<String, UninstallEntry> = HashMap::new();
 
@@ -95,13 +113,21 @@ fn merge_uninstall_entry(existing: &mut UninstallEntry, incoming: &UninstallEntr
     if existing.display_icon.is_none() {
         existing.display_icon = incoming.display_icon.clone();
     }
+    if existing.uninstall_string.is_none() {
+        existing.uninstall_string = incoming.uninstall_string.clone();
+    }
+    for key in &incoming.registry_keys {
+        if !existing.registry_keys.contains(key) {
+            existing.registry_keys.push(key.clone());
+        }
+    }
     if entry_quality(incoming) > entry_quality(existing) {
         existing.name = incoming.name.clone();
         existing.publisher = incoming.publisher.clone();
     }
 }
 
-fn strip_version_suffix(name: &str) -> String {
+pub(super) fn strip_version_suffix(name: &str) -> String {
     let s = name.trim();
     if s.is_empty() {
         return String::new();
@@ -185,37 +211,87 @@ pub(super) fn get_install_dir_hint(uninstall: &UninstallEntry) -> Option<PathBuf
             return Some(p);
         }
     }
-    if let Some(icon) = uninstall.display_icon.as_deref() {
-        return parse_display_icon_to_dir(icon);
+    let icon = display_icon_path(uninstall.display_icon.as_deref()?)?;
+    if icon.is_dir() {
+        return Some(icon);
     }
-    None
+    icon.parent().filter(|_| icon.is_file()).map(Path::to_path_buf)
 }
 
-fn parse_display_icon_to_dir(display_icon: &str) -> Option<PathBuf> {
-    let mut s = display_icon.trim().to_string();
-    if s.starts_with('"') && s.ends_with('"') && s.len() >= 2 {
-        s = s[1..s.len() - 1].to_string();
+/// `DisplayIcon` 指向的文件：去掉引号与 `,图标序号`，展开环境变量。
+/// 只有文件名、需按系统搜索路径解析的返回 `None`。
+pub(super) fn display_icon_path(display_icon: &str) -> Option<PathBuf> {
+    let s = display_icon.trim().trim_matches('"');
+    let s = match s.rsplit_once(',') {
+        Some((left, right)) if right.trim().trim_start_matches('-').parse::<i32>().is_ok() => left,
+        _ => s,
+    };
+    let s = expand_env_vars(s.trim().trim_matches('"'));
+    is_absolute_windows_path(&s).then(|| PathBuf::from(s))
+}
+
+/// `UninstallString` 中要运行的程序。没有引号时路径里的空格无法区分，
+/// 依次尝试更长的前缀，取第一个存在的文件；都不存在时取到 `.exe` 为止的部分。
+/// `MsiExec.exe` 这类只有文件名的命令返回 `None`。
+pub(super) fn command_executable(command: &str) -> Option<PathBuf> {
+    let command = expand_env_vars(command.trim());
+    if let Some(rest) = command.strip_prefix('"') {
+        let exe = rest.split('"').next().unwrap_or(rest);
+        return is_absolute_windows_path(exe).then(|| PathBuf::from(exe));
+    }
+    if !is_absolute_windows_path(&command) {
+        return None;
     }
 
-    if let Some((left, _right)) = s.rsplit_once(',') {
-        let candidate = left.trim();
-        if candidate.contains(":\\") {
-            s = candidate.to_string();
+    let prefixes = command
+        .char_indices()
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(i, _)| &command[..i])
+        .chain(std::iter::once(command.as_str()));
+    for prefix in prefixes {
+        if Path::new(prefix).is_file() {
+            return Some(PathBuf::from(prefix));
         }
     }
+    let end = command
+        .to_ascii_lowercase()
+        .find(".exe")
+        .map(|i| i + ".exe".len())
+        .or_else(|| command.find(char::is_whitespace))
+        .unwrap_or(command.len());
+    Some(PathBuf::from(&command[..end]))
+}
 
-    let p = PathBuf::from(s.trim());
-    if p.is_dir() {
-        return Some(p);
-    }
-    if p.is_file() {
-        return p.parent().map(|d| d.to_path_buf());
+/// 盘符路径（`C:\`）或 UNC 路径。
+fn is_absolute_windows_path(s: &str) -> bool {
+    let b = s.as_bytes();
+    s.starts_with(r"\\") || (b.len() >= 3 && b[0].is_ascii_alphabetic() && &b[1..3] == b":\\")
+}
+
+/// 展开 `%NAME%` 形式的环境变量；未定义的变量原样保留。
+fn expand_env_vars(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('%') {
+        let Some(len) = rest[start + 1..].find('%') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        out.push_str(&rest[..start]);
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => out.push_str(&value),
+            _ => out.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
     }
-    None
+    out.push_str(rest);
+    out
 }
 
+#[cfg(windows)]
 fn read_uninstall_key(
     root: &winreg::RegKey,
+    root_name: &str,
     subkey_path: &str,
     flags: u32,
     id_prefix: &str,
@@ -282,7 +358,14 @@ fn read_uninstall_key(
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty());
 
+        let uninstall_string: Option<String> = sub
+            .get_value::<String, _>("UninstallString")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
         let id = format!("{id_prefix}:{subkey_name}");
+        let registry_key = format!(r"{root_name}\{subkey_path}\{subkey_name}");
 
         out.push(UninstallEntry {
             id,
@@ -291,8 +374,81 @@ fn read_uninstall_key(
             estimated_bytes,
             install_location,
             display_icon,
+            uninstall_string,
+            registry_keys: vec![registry_key],
         });
     }
 
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, registry_key: &str) -> UninstallEntry {
+        UninstallEntry {
+            id: id.to_string(),
+            name: "Contoso Viewer 1.2".to_string(),
+            publisher: Some("Contoso Ltd.".to_string()),
+            estimated_bytes: 0,
+            install_location: None,
+            display_icon: None,
+            uninstall_string: None,
+            registry_keys: vec![registry_key.to_string()],
+        }
+    }
+
+    #[test]
+    fn deduped_entry_keeps_every_registry_key() {
+        let merged = dedupe_uninstall_entries(vec![
+            entry("hklm:viewer", r"HKEY_LOCAL_MACHINE\SOFTWARE\Uninstall\Viewer"),
+            entry("hkcu:viewer", r"HKEY_CURRENT_USER\SOFTWARE\Uninstall\Viewer"),
+        ]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            merged[0].registry_keys,
+            [
+                r"HKEY_LOCAL_MACHINE\SOFTWARE\Uninstall\Viewer",
+                r"HKEY_CURRENT_USER\SOFTWARE\Uninstall\Viewer",
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_defined_env_vars_and_keeps_the_rest() {
+        let name = format!("APPMANAGER_TEST_DIR_{}", std::process::id());
+        std::env::set_var(&name, r"D:\Apps");
+        let expanded = expand_env_vars(&format!(r"%{name}%\Viewer\%UNDEFINED_APPMANAGER%\%%"));
+        std::env::remove_var(&name);
+
+        assert_eq!(expanded, r"D:\Apps\Viewer\%UNDEFINED_APPMANAGER%\%%");
+        assert_eq!(expand_env_vars("100% done"), "100% done");
+    }
+
+    #[test]
+    fn command_executable_handles_quotes_spaces_and_bare_names() {
+        let exe = |s: &str| command_executable(s).map(|p| p.to_string_lossy().to_string());
+        assert_eq!(
+            exe(r#""C:\Program Files\Viewer\uninst.exe" /S"#).as_deref(),
+            Some(r"C:\Program Files\Viewer\uninst.exe")
+        );
+        assert_eq!(
+            exe(r"C:\Program Files\Viewer\Uninstall.EXE /S").as_deref(),
+            Some(r"C:\Program Files\Viewer\Uninstall.EXE")
+        );
+        assert_eq!(exe(r"C:\Tools\remove --quiet").as_deref(), Some(r"C:\Tools\remove"));
+        assert_eq!(exe(r"\\server\share\setup.exe").as_deref(), Some(r"\\server\share\setup.exe"));
+        assert_eq!(exe("MsiExec.exe /X{12345678-0000-0000-0000-000000000000}"), None);
+        assert_eq!(exe(r#""uninst.exe" /S"#), None);
+    }
+
+    #[test]
+    fn display_icon_path_strips_quotes_and_icon_index() {
+        let icon = |s: &str| display_icon_path(s).map(|p| p.to_string_lossy().to_string());
+        assert_eq!(icon(r#""C:\Viewer\viewer.exe",0"#).as_deref(), Some(r"C:\Viewer\viewer.exe"));
+        assert_eq!(icon(r"C:\Viewer\viewer.exe,-101").as_deref(), Some(r"C:\Viewer\viewer.exe"));
+        assert_eq!(icon(r"C:\Viewer, Inc\app.ico").as_deref(), Some(r"C:\Viewer, Inc\app.ico"));
+        assert_eq!(icon("viewer.ico"), None);
+    }
+}
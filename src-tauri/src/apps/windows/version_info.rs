@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::roots::{RootFolders, Roots};
+use super::uninstall::{display_icon_path, get_install_dir_hint, UninstallEntry};
+use crate::apps::pe::{read_version_info, VersionInfo};
+
+const MAX_EXES_PER_FOLDER: usize = 8;
+
+/// 各根目录下顶层文件夹中可执行文件的版本资源，按文件夹 key 索引。
+#[derive(Default)]
+pub(super) struct FolderEvidence {
+    pub(super) local: HashMap<String, Vec<VersionInfo>>,
+    pub(super) roaming: HashMap<String, Vec<VersionInfo>>,
+    pub(super) local_low: HashMap<String, Vec<VersionInfo>>,
+    pub(super) program_data: HashMap<String, Vec<VersionInfo>>,
+}
+
+pub(super) fn collect_folder_evidence(roots: &Roots) -> FolderEvidence {
+    FolderEvidence {
+        local: collect_for_root(roots.local.as_ref()),
+        roaming: collect_for_root(roots.roaming.as_ref()),
+        local_low: collect_for_root(roots.local_low.as_ref()),
+        program_data: collect_for_root(roots.program_data.as_ref()),
+    }
+}
+
+fn collect_for_root(root: Option<&RootFolders>) -> HashMap<String, Vec<VersionInfo>> {
+    let Some(root) = root else {
+        return HashMap::new();
+    };
+
+    let mut out = HashMap::new();
+    for (key, dir) in &root.folders {
+        let infos: Vec<VersionInfo> = find_executables(dir, 1)
+            .iter()
+            .filter_map(|p| read_version_info(p))
+            .filter(|v| v.product_name.is_some() || v.file_description.is_some())
+            .collect();
+        if !infos.is_empty() {
+            out.insert(key.clone(), infos);
+        }
+    }
+    out
+}
+
+/// 注册表缺少 Publisher 时，用主程序的 CompanyName 补全。
+pub(super) fn fill_missing_publishers(entries: &mut [UninstallEntry]) {
+    for e in entries.iter_mut().filter(|e| e.publisher.is_none()) {
+        let mut candidates = Vec::new();
+        if let Some(exe) = display_icon_exe(e) {
+            candidates.push(exe);
+        }
+        if let Some(dir) = get_install_dir_hint(e) {
+            candidates.extend(find_executables(&dir, 0));
+        }
+
+        e.publisher = candidates
+            .iter()
+            .filter_map(|p| read_version_info(p))
+            .find_map(|v| v.company_name);
+    }
+}
+
+fn display_icon_exe(e: &UninstallEntry) -> Option<PathBuf> {
+    let p = display_icon_path(e.display_icon.as_deref()?)?;
+    let is_exe = p
+        .extension()
+        .map(|x| x.eq_ignore_ascii_case("exe"))
+        .unwrap_or(false);
+    (is_exe && p.is_file()).then_some(p)
+}
+
+/// 收集目录（以及 `depth` 层以内子目录）中的 .exe，按体积从大到小排列。
+fn find_executables(dir: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut found: Vec<(u64, PathBuf)> = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), 0usize)];
+
+    while let Some((d, level)) = pending.pop() {
+        let Ok(rd) = std::fs::read_dir(&d) else {
+            continue;
+        };
+        for e in rd.flatten() {
+            let Ok(ft) = e.file_type() else {
+                continue;
+            };
+            let p = e.path();
+            if ft.is_dir() {
+                if level < depth {
+                    pending.push((p, level + 1));
+                }
+                continue;
+            }
+            let is_exe = p
+                .extension()
+                .map(|x| x.eq_ignore_ascii_case("exe"))
+                .unwrap_or(false);
+            if ft.is_file() && is_exe {
+                let len = e.metadata().map(|m| m.len()).unwrap_or(0);
+                found.push((len, p));
+            }
+        }
+    }
+
+    found.sort_by_key(|(len, _)| std::cmp::Reverse(*len));
+    found
+        .into_iter()
+        .take(MAX_EXES_PER_FOLDER)
+        .map(|(_, p)| p)
+        .collect()
+}
@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use super::sizing::SizePass;
+use super::uninstall::get_install_dir_hint;
+use super::{enrich_with_breakdown, ScanSnapshot};
+use crate::apps::{AppRecord, CancelToken, ScanOptions};
+
+/// 连续变化（如解压、批量删除）静止该时长后才重新统计。
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+/// 变化持续不断时，最早一条未处理的变化等待超过该时长也立即重新统计，不再一直推迟。
+const MAX_LATENCY: Duration = Duration::from_secs(10);
+/// 未处理路径超过该数量时合并到所在的监视目录，批量变化时不无限占用内存。
+const MAX_PENDING: usize = 4096;
+/// 不存在或已被删除的监视目录按该间隔重新尝试注册。
+const REWATCH_INTERVAL: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+/// 索引写盘的最小间隔，避免频繁变化时反复重写整个索引文件。
+const INDEX_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(super) fn watch_snapshot<F>(
+    snapshot: &ScanSnapshot,
+    options: &ScanOptions,
+    stop: &CancelToken,
+    mut on_update: F,
+) where
+    F: FnMut(AppRecord),
+{
+    let mut targets = watch_targets(snapshot);
+    if targets.is_empty() && snapshot.roots.paths().is_empty() {
+        return;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let Ok(mut watcher) = notify::recommended_watcher(tx) else {
+        return;
+    };
+    // 根目录只监视本层：扫描时还不存在、之后才创建的文件夹也能触发重新统计。
+    for root in snapshot.roots.paths() {
+        let _ = watcher.watch(&root, RecursiveMode::NonRecursive);
+    }
+    let mut tops = top_level_paths(&targets);
+    let mut watched: HashSet<PathBuf> = HashSet::new();
+    rewatch(&mut watcher, &tops, &mut watched);
+
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+    // 合并后的监视目录：不知道其下具体哪些目录变化，整个子树都要重新统计。
+    let mut pending_subtrees: HashSet<PathBuf> = HashSet::new();
+    let mut first_pending: Option<Instant> = None;
+    let mut last_event = Instant::now();
+    let mut last_rewatch = Instant::now();
+    let mut last_save = Instant::now();
+    let mut index_dirty = false;
+
+    while !stop.is_cancelled() {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => {
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    pending.extend(event.paths);
+                    if pending.len() > MAX_PENDING {
+                        pending_subtrees.extend(coarsen(&mut pending, &tops));
+                    }
+                    first_pending.get_or_insert_with(Instant::now);
+                    last_event = Instant::now();
+                }
+            }
+            Ok(Err(_)) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let mut due = first_pending
+            .is_some_and(|t| last_event.elapsed() >= DEBOUNCE || t.elapsed() >= MAX_LATENCY);
+        let tick = last_rewatch.elapsed() >= REWATCH_INTERVAL;
+        if tick {
+            // 手动归属等会改变快照中的归属，定期按快照重建监视目录。
+            targets = watch_targets(snapshot);
+            tops = top_level_paths(&targets);
+        }
+        if due || tick {
+            // 新增或重新注册的目录在未被监视期间可能已有变化，整体重新统计。
+            let added = rewatch(&mut watcher, &tops, &mut watched);
+            if !added.is_empty() {
+                pending_subtrees.extend(added);
+                due = true;
+            }
+            last_rewatch = Instant::now();
+        }
+
+        if due {
+            first_pending = None;
+            let touched: Vec<PathBuf> = pending.drain().collect();
+            let subtrees: Vec<PathBuf> = pending_subtrees.drain().collect();
+            let records = refresh_touched(snapshot, &targets, &touched, &subtrees, options);
+            if stop.is_cancelled() {
+                break;
+            }
+            for record in records {
+                on_update(record);
+            }
+            index_dirty = true;
+        }
+
+        if index_dirty && last_save.elapsed() >= INDEX_SAVE_INTERVAL {
+            let _ = snapshot.size_cache.save_index();
+            index_dirty = false;
+            last_save = Instant::now();
+        }
+    }
+
+    if index_dirty {
+        let _ = snapshot.size_cache.save_index();
+    }
+}
+
+/// 每个被统计过的目录及其所属软件在 `snapshot.uninstall` 中的下标。
+fn watch_targets(snapshot: &ScanSnapshot) -> Vec<(PathBuf, Vec<usize>)> {
+    let assigned = snapshot.assigned();
+    let mut out: Vec<(PathBuf, Vec<usize>)> = Vec::new();
+    let mut add = |path: &Path, app: usize| match out.iter_mut().find(|(p, _)| p == path) {
+        Some((_, apps)) => {
+            if !apps.contains(&app) {
+                apps.push(app);
+            }
+        }
+        None => out.push((path.to_path_buf(), vec![app])),
+    };
+
+    for (i, u) in snapshot.uninstall.iter().enumerate() {
+        if u.estimated_bytes == 0 {
+            if let Some(dir) = get_install_dir_hint(u) {
+                add(&dir, i);
+            }
+        }
+        for map in [
+            &assigned.local,
+            &assigned.roaming,
+            &assigned.local_low,
+            &assigned.program_data,
+            &assigned.extra,
+        ] {
+            for p in map.get(&u.id).into_iter().flatten() {
+                add(p, i);
+            }
+        }
+        for folder in &assigned.shared {
+            if folder.owners.iter().any(|o| o.app_id == u.id) {
+                add(&folder.path, i);
+            }
+        }
+    }
+    out
+}
+
+/// 注册尚未监视的目录，返回本次新注册成功的目录。
+/// 已被删除或不再需要监视的目录先注销，已删除的待重新创建后再注册。
+fn rewatch(
+    watcher: &mut impl Watcher,
+    tops: &[PathBuf],
+    watched: &mut HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+    watched.retain(|p| {
+        let alive = p.is_dir() && tops.contains(p);
+        if !alive {
+            let _ = watcher.unwatch(p);
+        }
+        alive
+    });
+    let mut added = Vec::new();
+    for p in tops {
+        if !watched.contains(p) && p.is_dir() && watcher.watch(p, RecursiveMode::Recursive).is_ok()
+        {
+            watched.insert(p.clone());
+            added.push(p.clone());
+        }
+    }
+    added
+}
+
+/// 把监视目录下的未处理路径从 `pending` 中取出，合并为所在的监视目录。
+/// 根目录本层的变化只来自非递归监视，数量受根目录子项数限制，原样保留。
+fn coarsen(pending: &mut HashSet<PathBuf>, tops: &[PathBuf]) -> HashSet<PathBuf> {
+    let mut subtrees = HashSet::new();
+    pending.retain(|p| match tops.iter().find(|t| p.starts_with(t)) {
+        Some(t) => {
+            subtrees.insert(t.clone());
+            false
+        }
+        None => true,
+    });
+    subtrees
+}
+
+/// 嵌套在其他监视目录之下的路径无需单独监视。
+fn top_level_paths(targets: &[(PathBuf, Vec<usize>)]) -> Vec<PathBuf> {
+    let mut paths: Vec<&PathBuf> = targets.iter().map(|(p, _)| p).collect();
+    paths.sort_by_key(|p| p.components().count());
+    let mut out: Vec<PathBuf> = Vec::new();
+    for p in paths {
+        if !out.iter().any(|o| p.starts_with(o)) {
+            out.push(p.clone());
+        }
+    }
+    out
+}
+
+fn refresh_touched(
+    snapshot: &ScanSnapshot,
+    targets: &[(PathBuf, Vec<usize>)],
+    touched: &[PathBuf],
+    subtrees: &[PathBuf],
+    options: &ScanOptions,
+) -> Vec<AppRecord> {
+    for t in touched {
+        snapshot.size_cache.invalidate(t);
+    }
+    for t in subtrees {
+        snapshot.size_cache.invalidate_subtree(t);
+    }
+    let mut affected: Vec<usize> = Vec::new();
+    for t in touched.iter().chain(subtrees) {
+        for (root, apps) in targets {
+            if t.starts_with(root) {
+                for a in apps {
+                    if !affected.contains(a) {
+                        affected.push(*a);
+                    }
+                }
+            }
+        }
+    }
+    affected.sort();
+
+    affected
+        .into_iter()
+        .map(|i| {
+            let record = enrich_with_breakdown(
+                snapshot.uninstall[i].clone(),
+                &snapshot.assigned(),
+                options,
+                &snapshot.size_cache,
+                SizePass::Exact,
+            );
+            snapshot.remember(&record);
+            record
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_targets_share_one_watch() {
+        let targets = vec![
+            (PathBuf::from("/root/a/b"), vec![0]),
+            (PathBuf::from("/root/a"), vec![1]),
+            (PathBuf::from("/root/c"), vec![2]),
+        ];
+        let mut tops = top_level_paths(&targets);
+        tops.sort();
+        assert_eq!(tops, [PathBuf::from("/root/a"), PathBuf::from("/root/c")]);
+    }
+
+    #[test]
+    fn coarsen_collapses_to_watched_dirs() {
+        let tops = [PathBuf::from("/root/a"), PathBuf::from("/root/c")];
+        let mut pending: HashSet<PathBuf> = (0..100)
+            .map(|i| PathBuf::from(format!("/root/a/x/{i}.tmp")))
+            .chain([PathBuf::from("/root/c/y"), PathBuf::from("/root/new")])
+            .collect();
+        let coarse = coarsen(&mut pending, &tops);
+        let expected: HashSet<PathBuf> = ["/root/a", "/root/c"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(coarse, expected);
+        assert_eq!(pending, HashSet::from([PathBuf::from("/root/new")]));
+    }
+}
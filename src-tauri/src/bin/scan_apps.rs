@@ -11,17 +11,254 @@ fn format_bytes(bytes: u64) -> String {
 }
 
 fn main() {
-    let mut apps = appmanager_lib::apps::scan_apps();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("eval") => {
+            let Some(path) = args.get(1) else {
+                eval_usage();
+            };
+            run_eval(path, &parse_match_params(&args[2..]));
+        }
+        Some("index") => run_index(args.get(1).map(String::as_str)),
+        Some("duplicates") => run_duplicates(),
+        _ => run_scan(parse_scan_options(&args)),
+    }
+}
+
+fn scan_usage() -> ! {
+    eprintln!(
+        "usage: scan_apps [--approximate <kind,kind,...>] [--links skip|link-only|follow]\n\
+         \x20                [--background] [--max-files-per-sec <n>] [--max-bytes-per-sec <n>]"
+    );
+    std::process::exit(2);
+}
+
+fn parse_scan_options(args: &[String]) -> appmanager_lib::apps::ScanOptions {
+    use appmanager_lib::apps::LinkPolicy;
+
+    let mut options = appmanager_lib::apps::ScanOptions::default();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let mut value = || {
+            rest.next()
+                .map(String::as_str)
+                .unwrap_or_else(|| scan_usage())
+        };
+        match flag.as_str() {
+            "--approximate" => {
+                options.approximate = value().split(',').map(str::to_string).collect();
+            }
+            "--links" => {
+                options.link_policy = match value() {
+                    "skip" => LinkPolicy::Skip,
+                    "link-only" => LinkPolicy::LinkOnly,
+                    "follow" => LinkPolicy::Follow,
+                    _ => scan_usage(),
+                };
+            }
+            // 限速选项隐含后台模式。
+            "--background" => {
+                options.background.get_or_insert_with(Default::default);
+            }
+            "--max-files-per-sec" => {
+                let n = value().parse().unwrap_or_else(|_| scan_usage());
+                options
+                    .background
+                    .get_or_insert_with(Default::default)
+                    .max_files_per_sec = n;
+            }
+            "--max-bytes-per-sec" => {
+                let n = value().parse().unwrap_or_else(|_| scan_usage());
+                options
+                    .background
+                    .get_or_insert_with(Default::default)
+                    .max_bytes_per_sec = n;
+            }
+            _ => scan_usage(),
+        }
+    }
+    options
+}
+
+fn eval_usage() -> ! {
+    eprintln!(
+        "usage: scan_apps eval <dataset.json> [--name-threshold <n>] [--publisher-threshold <n>]"
+    );
+    std::process::exit(2);
+}
+
+/// 未指定的阈值取扫描时使用的默认值。
+fn parse_match_params(args: &[String]) -> appmanager_lib::apps::MatchParams {
+    let mut params = appmanager_lib::apps::MatchParams::default();
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest
+            .next()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| eval_usage());
+        match flag.as_str() {
+            "--name-threshold" => params.name_threshold = value,
+            "--publisher-threshold" => params.publisher_threshold = value,
+            _ => eval_usage(),
+        }
+    }
+    params
+}
+
+fn run_index(action: Option<&str>) {
+    if action == Some("clear") {
+        if let Err(e) = appmanager_lib::apps::clear_size_index() {
+            eprintln!("failed to clear size index: {e}");
+            std::process::exit(1);
+        }
+        println!("size index cleared");
+        return;
+    }
+
+    let info = appmanager_lib::apps::size_index_info();
+    println!("path:        {}", info.path.as_deref().unwrap_or("-"));
+    println!("directories: {}", info.directory_count);
+    println!("file size:   {}", format_bytes(info.index_bytes));
+    match info.updated_at {
+        Some(t) => println!("updated at:  {t}"),
+        None => println!("updated at:  -"),
+    }
+}
+
+fn run_duplicates() {
+    let cancel = appmanager_lib::apps::CancelToken::default();
+    let options = appmanager_lib::apps::ScanOptions::default();
+    let Some(snapshot) = appmanager_lib::apps::scan_apps_stream(&options, &cancel, |_| {}, |_| {})
+    else {
+        return;
+    };
+
+    let dup_options = appmanager_lib::apps::DuplicateOptions::default();
+    let Some(report) =
+        appmanager_lib::apps::find_duplicates(&snapshot, &dup_options, &cancel, |p| {
+            eprint!("\r{:<8} {}/{}   ", p.phase, p.current, p.total);
+        })
+    else {
+        return;
+    };
+    eprintln!();
+
+    println!(
+        "files: {}  duplicate sets: {}  wasted: {}  reused hashes: {}",
+        report.files_scanned,
+        report.set_count,
+        format_bytes(report.wasted_bytes),
+        report.reused_hashes
+    );
+    for s in report.sets.iter().take(30) {
+        println!(
+            "{:<10}  x{}  {}",
+            format_bytes(s.wasted_bytes),
+            s.files.len(),
+            s.apps.join(", ")
+        );
+        for f in &s.files {
+            println!("            {}", f.path);
+        }
+    }
+}
+
+fn run_eval(path: &str, params: &appmanager_lib::apps::MatchParams) {
+    let dataset: appmanager_lib::apps::MatchEvalDataset = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("failed to load dataset {path}: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let report = appmanager_lib::apps::evaluate_matching(&dataset, params);
+    println!(
+        "thresholds: name={}  publisher={}",
+        params.name_threshold, params.publisher_threshold
+    );
+    println!(
+        "folders: {}  expected: {}  predicted: {}  correct: {}",
+        report.folder_count, report.expected_count, report.predicted_count, report.correct_count
+    );
+    println!("precision: {:.3}", report.precision);
+    println!("recall:    {:.3}", report.recall);
+
+    if !report.misattributions.is_empty() {
+        println!("misattributions: {}", report.misattributions.len());
+        for m in &report.misattributions {
+            println!(
+                "{:<16}  {:<32}  expected={:<24}  predicted={}",
+                m.root,
+                m.folder,
+                m.expected.as_deref().unwrap_or("-"),
+                m.predicted.as_deref().unwrap_or("-")
+            );
+        }
+    }
+}
+
+fn run_scan(options: appmanager_lib::apps::ScanOptions) {
+    let cancel = appmanager_lib::apps::CancelToken::default();
+    let mut apps = Vec::new();
+    let mut timings = Vec::new();
+    appmanager_lib::apps::scan_apps_stream(
+        &options,
+        &cancel,
+        |p| {
+            if let Some(w) = &p.walk {
+                let eta = w
+                    .eta_secs
+                    .map(|s| format!("  eta {s}s"))
+                    .unwrap_or_default();
+                eprint!(
+                    "\r{} files  {}  {:.0} files/s{eta}   ",
+                    w.files_seen,
+                    format_bytes(w.bytes_seen),
+                    w.files_per_sec
+                );
+            }
+            timings = p.phase_timings;
+        },
+        |r| {
+            if !r.estimated {
+                apps.push(r);
+            }
+        },
+    );
     apps.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes));
+    eprintln!();
+    for t in &timings {
+        eprintln!("{:<10} {} ms", t.phase, t.millis);
+    }
 
     println!("apps: {}", apps.len());
     for a in apps.iter().take(30) {
+        let error = a
+            .error_bytes
+            .map(|e| format!("±{}", format_bytes(e)))
+            .unwrap_or_default();
+        // 有路径无法读取时大小可能偏小。
+        let incomplete = if a.incomplete { "  (不完整)" } else { "" };
         println!(
-            "{:<10}  {:<40}  {:<28}  {}",
+            "{:<10}  {:<10}  {:<10}  {:<40}  {:<28}  {}{}",
             format_bytes(a.total_bytes),
+            error,
+            format_bytes(a.total_allocated_bytes),
             a.name,
             a.publisher.clone().unwrap_or_default(),
-            a.id
+            a.id,
+            incomplete
         );
+        for v in &a.followed_volumes {
+            println!("    另在 {} 上 {}", v.volume, format_bytes(v.bytes));
+        }
+        for f in a.breakdown.iter().flat_map(|b| &b.relocated) {
+            let bytes = f.bytes.map(format_bytes).unwrap_or_default();
+            println!("    {} -> {} ({}) {bytes}", f.path, f.target, f.volume);
+        }
     }
 }
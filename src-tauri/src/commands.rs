@@ -1,5 +1,57 @@
 use crate::apps;
-use tauri::Emitter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+
+static NEXT_SCAN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 当前扫描（及其后续文件监视）的 id 与取消标志。同一时间只保留一个扫描，
+/// 开始新的扫描会显式取代上一个。
+static CURRENT_SCAN: Mutex<Option<(u64, apps::CancelToken)>> = Mutex::new(None);
+
+/// 正在进行的重复文件查找的 id 与取消标志；id 与扫描共用同一计数器。
+static CURRENT_DUPLICATES: Mutex<Option<(u64, apps::CancelToken)>> = Mutex::new(None);
+
+/// 最近一次完成的扫描及其 id，由 Tauri 托管。审计、逐层浏览与重复文件查找都基于同一份
+/// 软件列表、归属结果与大小缓存，彼此一致，也不必重新遍历。
+#[derive(Default)]
+pub struct ScanState {
+    last: Mutex<Option<(u64, Arc<apps::ScanSnapshot>)>>,
+}
+
+impl ScanState {
+    fn snapshot(&self) -> Result<Arc<apps::ScanSnapshot>, String> {
+        self.last
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .map(|(_, s)| Arc::clone(s))
+            .ok_or_else(|| "请先完成一次扫描".to_string())
+    }
+
+    fn set(&self, scan_id: u64, snapshot: Option<Arc<apps::ScanSnapshot>>) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = snapshot.map(|s| (scan_id, s));
+        }
+    }
+
+    /// 只清除指定扫描的结果，已被新扫描取代时不受影响。
+    fn clear_if(&self, scan_id: u64) {
+        if let Ok(mut last) = self.last.lock() {
+            if last.as_ref().is_some_and(|(id, _)| *id == scan_id) {
+                *last = None;
+            }
+        }
+    }
+}
+
+/// 所有扫描事件都带上扫描 id，前端据此丢弃已被取代的扫描发出的事件。
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanEvent<T> {
+    scan_id: u64,
+    data: T,
+}
 
 #[tauri::command]
 pub fn greet(name: &str) -> String {
@@ -16,28 +68,221 @@ pub fn get_disk_info() -> Vec<apps::DiskInfo> {
     apps::get_disk_info()
 }
 
+/// 基于最近一次扫描生成审计报告；尚未完成扫描时返回错误。
+#[tauri::command]
+pub async fn get_audit_overview(
+    state: tauri::State<'_, ScanState>,
+    options: Option<apps::AuditOptions>,
+) -> Result<apps::AuditOverview, String> {
+    let snapshot = state.snapshot()?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || apps::audit_overview(&snapshot, &options))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 统计审计报告中某个未关联文件夹的大小，复用最近一次扫描的大小缓存。
+#[tauri::command]
+pub async fn measure_audit_folder_size(
+    state: tauri::State<'_, ScanState>,
+    kind: String,
+    folder: String,
+) -> Result<u64, String> {
+    let snapshot = state.snapshot()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        apps::measure_audit_folder_size(&snapshot, &kind, &folder)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 把审计报告中的文件夹归属到指定软件，返回该软件更新后的记录。
+#[tauri::command]
+pub async fn assign_audit_folder(
+    state: tauri::State<'_, ScanState>,
+    kind: String,
+    folder: String,
+    app_id: String,
+) -> Result<apps::AppRecord, String> {
+    let snapshot = state.snapshot()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        apps::assign_folder(&snapshot, &kind, &folder, &app_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn export_corrections() -> String {
+    apps::export_corrections()
+}
+
+#[tauri::command]
+pub fn import_corrections(json: String) -> Result<u32, String> {
+    apps::import_corrections(&json)
+}
+
 #[tauri::command]
-pub fn get_audit_overview() -> apps::AuditOverview {
-    apps::audit_overview()
+pub fn get_size_index_info() -> apps::SizeIndexInfo {
+    apps::size_index_info()
 }
 
 #[tauri::command]
-pub fn measure_audit_folder_size(kind: String, folder: String) -> u64 {
-    apps::measure_folder_size(&kind, &folder)
+pub fn clear_size_index() -> Result<(), String> {
+    apps::clear_size_index()
 }
 
+/// 浏览最近一次扫描中某个软件的明细条目；`path` 为空时列出该条目的顶层目录。
 #[tauri::command]
-pub async fn start_scan_apps(app: tauri::AppHandle) -> Result<(), String> {
+pub async fn drill_down_app(
+    state: tauri::State<'_, ScanState>,
+    app_id: String,
+    kind: String,
+    path: Option<String>,
+    limit: Option<u32>,
+) -> Result<apps::DrillDownLevel, String> {
+    let snapshot = state.snapshot()?;
     tauri::async_runtime::spawn_blocking(move || {
-        apps::scan_apps_stream(
+        apps::drill_down(&snapshot, &app_id, &kind, path.as_deref(), limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 在最近一次扫描的归因目录中查找重复文件并返回任务 id；进行中的查找会被取代。
+/// 进度、结果与取消分别通过 `duplicate_progress`、`duplicate_done`、`duplicate_cancelled` 事件发出。
+#[tauri::command]
+pub async fn start_find_duplicates(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ScanState>,
+    options: Option<apps::DuplicateOptions>,
+) -> Result<u64, String> {
+    let options = options.unwrap_or_default();
+    let snapshot = state.snapshot()?;
+    let scan_id = NEXT_SCAN_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = apps::CancelToken::default();
+    {
+        let mut current = CURRENT_DUPLICATES.lock().map_err(|e| e.to_string())?;
+        if let Some((_, previous)) = current.replace((scan_id, cancel.clone())) {
+            previous.cancel();
+        }
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let report = apps::find_duplicates(&snapshot, &options, &cancel, |p| {
+            let _ = app.emit("duplicate_progress", ScanEvent { scan_id, data: p });
+        });
+        match report {
+            Some(r) => {
+                let _ = app.emit("duplicate_done", ScanEvent { scan_id, data: r });
+            }
+            None => {
+                let _ = app.emit("duplicate_cancelled", ScanEvent { scan_id, data: () });
+            }
+        }
+        if let Ok(mut current) = CURRENT_DUPLICATES.lock() {
+            if current.as_ref().is_some_and(|(id, _)| *id == scan_id) {
+                *current = None;
+            }
+        }
+    });
+    Ok(scan_id)
+}
+
+/// 取消重复文件查找；已算出的哈希会保留，下次查找从中断处继续。
+#[tauri::command]
+pub fn cancel_find_duplicates(id: u64) -> Result<(), String> {
+    let mut current = CURRENT_DUPLICATES.lock().map_err(|e| e.to_string())?;
+    match current.as_ref() {
+        Some((job_id, cancel)) if *job_id == id => {
+            cancel.cancel();
+            *current = None;
+            Ok(())
+        }
+        _ => Err(format!("查找任务 {id} 不存在或已结束")),
+    }
+}
+
+#[tauri::command]
+pub async fn start_scan_apps(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ScanState>,
+    options: Option<apps::ScanOptions>,
+) -> Result<u64, String> {
+    let options = options.unwrap_or_default();
+    let scan_id = NEXT_SCAN_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = apps::CancelToken::default();
+    {
+        let mut current = CURRENT_SCAN.lock().map_err(|e| e.to_string())?;
+        if let Some((_, previous)) = current.replace((scan_id, cancel.clone())) {
+            previous.cancel();
+        }
+    }
+    state.set(scan_id, None);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let snapshot = apps::scan_apps_stream(
+            &options,
+            &cancel,
             |p| {
-                let _ = app.emit("scan_progress", p);
+                let _ = app.emit("scan_progress", ScanEvent { scan_id, data: p });
             },
             |r| {
-                let _ = app.emit("scan_result", r);
+                let _ = app.emit("scan_result", ScanEvent { scan_id, data: r });
             },
         );
-        let _ = app.emit("scan_done", ());
+        let Some(snapshot) = snapshot else {
+            let _ = app.emit("scan_cancelled", ScanEvent { scan_id, data: () });
+            return;
+        };
+        let snapshot = Arc::new(snapshot);
+        if !cancel.is_cancelled() {
+            app.state::<ScanState>()
+                .set(scan_id, Some(Arc::clone(&snapshot)));
+        }
+        let _ = app.emit("scan_done", ScanEvent { scan_id, data: () });
+
+        apps::watch_apps(&snapshot, &options, &cancel, |r| {
+            let _ = app.emit("scan_update", ScanEvent { scan_id, data: r });
+        });
     });
-    Ok(())
+    Ok(scan_id)
+}
+
+/// 取消扫描；扫描已完成时则停止其文件监视。
+#[tauri::command]
+pub fn cancel_scan(state: tauri::State<'_, ScanState>, id: u64) -> Result<(), String> {
+    let mut current = CURRENT_SCAN.lock().map_err(|e| e.to_string())?;
+    match current.as_ref() {
+        Some((scan_id, cancel)) if *scan_id == id => {
+            cancel.cancel();
+            *current = None;
+            // 已取消的大小缓存无法再遍历新目录，浏览与审计结果会不完整。
+            state.clear_if(id);
+            Ok(())
+        }
+        _ => Err(format!("扫描 {id} 不存在或已被取代")),
+    }
+}
+
+/// 暂停扫描：目录遍历在处理下一个目录前等待，直到 `resume_scan` 或取消。
+#[tauri::command]
+pub fn pause_scan(id: u64) -> Result<(), String> {
+    with_current_scan(id, apps::CancelToken::pause)
+}
+
+#[tauri::command]
+pub fn resume_scan(id: u64) -> Result<(), String> {
+    with_current_scan(id, apps::CancelToken::resume)
+}
+
+fn with_current_scan(id: u64, f: impl FnOnce(&apps::CancelToken)) -> Result<(), String> {
+    let current = CURRENT_SCAN.lock().map_err(|e| e.to_string())?;
+    match current.as_ref() {
+        Some((scan_id, cancel)) if *scan_id == id => {
+            f(cancel);
+            Ok(())
+        }
+        _ => Err(format!("扫描 {id} 不存在或已被取代")),
+    }
 }
@@ -2,20 +2,35 @@ pub mod apps;
 mod commands;
 
 use commands::{
-    get_audit_overview, get_disk_info, greet, measure_audit_folder_size, scan_apps, start_scan_apps,
+    assign_audit_folder, cancel_find_duplicates, cancel_scan, clear_size_index, drill_down_app,
+    export_corrections, get_audit_overview, get_disk_info, get_size_index_info, greet,
+    import_corrections, measure_audit_folder_size, pause_scan, resume_scan, scan_apps,
+    start_find_duplicates, start_scan_apps, ScanState,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(ScanState::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             scan_apps,
             get_disk_info,
             start_scan_apps,
+            cancel_scan,
+            pause_scan,
+            resume_scan,
+            drill_down_app,
+            start_find_duplicates,
+            cancel_find_duplicates,
             get_audit_overview,
-            measure_audit_folder_size
+            measure_audit_folder_size,
+            assign_audit_folder,
+            export_corrections,
+            import_corrections,
+            get_size_index_info,
+            clear_size_index
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -4,8 +4,13 @@ import { AuditPanel } from "./features/apps/components/AuditPanel";
 import { AppsList } from "./features/apps/components/AppsList";
 import { Dashboard } from "./features/apps/components/Dashboard";
 import { DiskOverview } from "./features/apps/components/DiskOverview";
+import { DuplicatesPanel } from "./features/apps/components/DuplicatesPanel";
+import { SizeIndexPanel } from "./features/apps/components/SizeIndexPanel";
+import { VendorOverview } from "./features/apps/components/VendorOverview";
 import { useAudit } from "./features/apps/useAudit";
+import { useDuplicates } from "./features/apps/useDuplicates";
 import { useScanApps } from "./features/apps/useScanApps";
+import type { LinkPolicy } from "./types/apps";
 
 function formatBytes(bytes: number) {
   if (!Number.isFinite(bytes) || bytes < 0) return "0 B";
@@ -20,19 +25,70 @@ function formatBytes(bytes: number) {
   return `${value.toFixed(digits)} ${units[unitIndex]}`;
 }
 
+function formatDuration(secs: number) {
+  if (secs < 60) return `${secs} 秒`;
+  const minutes = Math.floor(secs / 60);
+  if (minutes < 60) return `${minutes} 分 ${secs % 60} 秒`;
+  return `${Math.floor(minutes / 60)} 小时 ${minutes % 60} 分`;
+}
+
+const PHASE_LABELS: Record<string, string> = {
+  uninstall: "读取软件列表",
+  match: "归属匹配",
+  estimate: "估算",
+  scan: "精确统计",
+  save: "保存索引",
+};
+
+/** 可选择抽样统计的条目类型。 */
+const APPROXIMATE_KINDS = [
+  { kind: "extraData", label: "游戏库等附加数据" },
+  { kind: "program", label: "安装目录" },
+  { kind: "appDataLocal", label: "AppData/Local" },
+  { kind: "programData", label: "ProgramData" },
+];
+
+const LINK_POLICIES: { policy: LinkPolicy; label: string }[] = [
+  { policy: "skip", label: "不跟随" },
+  { policy: "linkOnly", label: "只计链接" },
+  { policy: "follow", label: "跟随并注明目标盘" },
+];
+
 function App() {
   const [query, setQuery] = useState("");
-  const { expanded, isScanning, progress, rows, scan, stats, toggleExpanded } = useScanApps();
+  const {
+    approximateKinds,
+    background,
+    cancel,
+    expanded,
+    isPaused,
+    isScanning,
+    linkPolicy,
+    progress,
+    rows,
+    scan,
+    setApproximateKinds,
+    setBackground,
+    setLinkPolicy,
+    stats,
+    toggleExpanded,
+    togglePause,
+    updateRow,
+  } = useScanApps();
   const {
     audit,
+    auditError,
     auditLoading,
     auditOpen,
-    auditSizes,
+    assignFolder,
+    downloadCorrections,
+    uploadCorrections,
     loadAudit,
-    measureAuditFolder,
     resetAudit,
     setAuditOpen,
-  } = useAudit();
+  } = useAudit(updateRow);
+  const duplicates = useDuplicates();
+  const { resetDuplicates } = duplicates;
 
   const filtered = useMemo(() => {
     const normalized = query.trim().toLowerCase();
@@ -49,7 +105,8 @@ function App() {
   useEffect(() => {
     if (!isScanning) return;
     resetAudit();
-  }, [isScanning, resetAudit]);
+    resetDuplicates();
+  }, [isScanning, resetAudit, resetDuplicates]);
 
   return (
     <div className="min-h-dvh bg-zinc-950 text-zinc-100">
@@ -67,14 +124,24 @@ function App() {
             </div>
           </div>
 
-          <button
-            type="button"
-            onClick={scan}
-            disabled={isScanning}
-            className="inline-flex h-10 items-center justify-center rounded-xl bg-white px-4 text-sm font-medium text-zinc-950 shadow-sm ring-1 ring-white/10 transition enabled:hover:bg-zinc-200 disabled:opacity-60"
-          >
-            {isScanning ? "扫描中…" : "开始扫描"}
-          </button>
+          <div className="flex items-center gap-2">
+            {isScanning ? (
+              <button
+                type="button"
+                onClick={togglePause}
+                className="inline-flex h-10 items-center justify-center rounded-xl bg-zinc-950/40 px-4 text-sm font-medium text-zinc-100 ring-1 ring-white/10 transition hover:bg-white/5"
+              >
+                {isPaused ? "继续" : "暂停"}
+              </button>
+            ) : null}
+            <button
+              type="button"
+              onClick={isScanning ? cancel : scan}
+              className="inline-flex h-10 items-center justify-center rounded-xl bg-white px-4 text-sm font-medium text-zinc-950 shadow-sm ring-1 ring-white/10 transition enabled:hover:bg-zinc-200 disabled:opacity-60"
+            >
+              {isScanning ? "停止扫描" : "开始扫描"}
+            </button>
+          </div>
         </div>
 
         <DiskOverview formatBytes={formatBytes} />
@@ -94,7 +161,9 @@ function App() {
               {isScanning ? (
                 <div className="flex flex-col gap-2">
                   <div className="flex items-center justify-between gap-3 text-xs text-zinc-500">
-                    <div className="truncate">{progress?.message ?? "准备扫描…"}</div>
+                    <div className="truncate">
+                      {isPaused ? "已暂停" : (progress?.message ?? "准备扫描…")}
+                    </div>
                     {progress && progress.total > 0 ? (
                       <div className="tabular-nums">
                         {progress.current}/{progress.total}
@@ -112,6 +181,30 @@ function App() {
                       }}
                     />
                   </div>
+                  {progress?.walk ? (
+                    <div className="flex items-center justify-between gap-3 text-[11px] text-zinc-500">
+                      <div className="truncate" title={progress.walk.currentPath}>
+                        {progress.walk.currentPath}
+                      </div>
+                      <div className="shrink-0 tabular-nums">
+                        {progress.walk.filesSeen} 项 · {formatBytes(progress.walk.bytesSeen)} ·{" "}
+                        {Math.round(progress.walk.filesPerSec)} 项/秒
+                        {progress.walk.etaSecs != null
+                          ? ` · 约剩 ${formatDuration(progress.walk.etaSecs)}`
+                          : ""}
+                      </div>
+                    </div>
+                  ) : null}
+                  {progress && progress.phaseTimings.length > 0 ? (
+                    <div className="text-[11px] text-zinc-600 tabular-nums">
+                      {progress.phaseTimings
+                        .map(
+                          (t) =>
+                            `${PHASE_LABELS[t.phase] ?? t.phase} ${(t.millis / 1000).toFixed(1)}s`,
+                        )
+                        .join(" · ")}
+                    </div>
+                  ) : null}
                 </div>
               ) : null}
               <input
@@ -120,6 +213,85 @@ function App() {
                 placeholder="按名称或厂商过滤…"
                 className="h-11 w-full rounded-xl bg-zinc-950/40 px-4 text-sm text-zinc-100 placeholder:text-zinc-500 ring-1 ring-white/10 outline-none focus:ring-2 focus:ring-white/20"
               />
+              <div className="flex flex-wrap items-center gap-1.5 text-[11px] text-zinc-500">
+                <span title="只精确统计上层目录，深层目录抽样推算并给出误差范围">
+                  抽样统计：
+                </span>
+                {APPROXIMATE_KINDS.map(({ kind, label }) => {
+                  const active = approximateKinds.includes(kind);
+                  return (
+                    <button
+                      key={kind}
+                      type="button"
+                      disabled={isScanning}
+                      onClick={() =>
+                        setApproximateKinds((prev) =>
+                          active ? prev.filter((k) => k !== kind) : [...prev, kind],
+                        )
+                      }
+                      className={[
+                        "rounded-md px-1.5 py-0.5 ring-1 transition disabled:opacity-60",
+                        active
+                          ? "bg-indigo-500/15 text-indigo-200 ring-indigo-500/30"
+                          : "bg-zinc-950/40 text-zinc-400 ring-white/10 enabled:hover:bg-white/5",
+                      ].join(" ")}
+                    >
+                      {label}
+                    </button>
+                  );
+                })}
+                <span
+                  className="ml-3"
+                  title="通过目录联接、符号链接或挂载点迁移到其他位置的文件夹"
+                >
+                  迁移的文件夹：
+                </span>
+                <select
+                  value={linkPolicy}
+                  disabled={isScanning}
+                  onChange={(e) => setLinkPolicy(e.currentTarget.value as LinkPolicy)}
+                  className="rounded-md bg-zinc-950/40 px-1.5 py-0.5 text-zinc-300 ring-1 ring-white/10 disabled:opacity-60"
+                >
+                  {LINK_POLICIES.map(({ policy, label }) => (
+                    <option key={policy} value={policy}>
+                      {label}
+                    </option>
+                  ))}
+                </select>
+                <label
+                  className="ml-3 inline-flex items-center gap-1"
+                  title="单线程、低优先级并限速扫描，不拖慢正在使用的其他程序"
+                >
+                  <input
+                    type="checkbox"
+                    checked={background != null}
+                    disabled={isScanning}
+                    onChange={(e) =>
+                      setBackground(e.currentTarget.checked ? { maxFilesPerSec: 500 } : null)
+                    }
+                  />
+                  后台模式
+                </label>
+                {background ? (
+                  <label className="inline-flex items-center gap-1">
+                    <input
+                      type="number"
+                      min={0}
+                      step={100}
+                      value={background.maxFilesPerSec ?? 0}
+                      disabled={isScanning}
+                      onChange={(e) =>
+                        setBackground({
+                          ...background,
+                          maxFilesPerSec: Math.max(0, Number(e.currentTarget.value) || 0),
+                        })
+                      }
+                      className="w-16 rounded-md bg-zinc-950/40 px-1.5 py-0.5 text-zinc-300 ring-1 ring-white/10 disabled:opacity-60"
+                    />
+                    文件/秒
+                  </label>
+                ) : null}
+              </div>
             </div>
 
             <AppsList
@@ -132,23 +304,39 @@ function App() {
 
             <AuditPanel
               audit={audit}
+              auditError={auditError}
               auditLoading={auditLoading}
               auditOpen={auditOpen}
-              auditSizes={auditSizes}
+              canLoad={!isScanning && rows.length > 0}
+              assignFolder={assignFolder}
+              downloadCorrections={downloadCorrections}
+              uploadCorrections={uploadCorrections}
               formatBytes={formatBytes}
               loadAudit={loadAudit}
-              measureAuditFolder={measureAuditFolder}
               setAuditOpen={setAuditOpen}
             />
+
+            <DuplicatesPanel
+              canFind={!isScanning && rows.length > 0}
+              error={duplicates.error}
+              formatBytes={formatBytes}
+              isFinding={duplicates.isFinding}
+              progress={duplicates.progress}
+              report={duplicates.report}
+              onCancel={duplicates.cancel}
+              onFind={duplicates.find}
+            />
           </div>
 
-          <div className="w-full shrink-0 lg:w-[360px]">
+          <div className="flex w-full shrink-0 flex-col gap-6 lg:w-[360px]">
             <Dashboard
               stats={stats}
               totalApps={rows.length}
               formatBytes={formatBytes}
               isScanning={isScanning}
             />
+            <VendorOverview vendors={stats.vendorData} formatBytes={formatBytes} />
+            <SizeIndexPanel formatBytes={formatBytes} isScanning={isScanning} />
           </div>
         </div>
       </div>
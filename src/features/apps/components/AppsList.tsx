@@ -1,6 +1,7 @@
 import { AnimatePresence, motion } from "framer-motion";
 import { ChevronDown } from "lucide-react";
 import type { AppRecord } from "../../../types/apps";
+import { BreakdownBrowser } from "./BreakdownBrowser";
 
 export function AppsList(props: {
   expanded: Record<string, boolean>;
@@ -52,10 +53,47 @@ export function AppsList(props: {
                       {r.publisher ? (
                         <div className="truncate text-xs text-zinc-500">{r.publisher}</div>
                       ) : null}
+                      {r.lastActivity != null ? (
+                        <div className="truncate text-xs text-zinc-600">
+                          最后活动 {new Date(r.lastActivity * 1000).toLocaleDateString()}
+                        </div>
+                      ) : null}
                     </div>
                     <div className="flex items-center gap-3">
-                      <div className="text-sm tabular-nums text-zinc-200">
-                        {formatBytes(r.totalBytes)}
+                      <div className="text-right">
+                        <div
+                          className={[
+                            "text-sm tabular-nums",
+                            r.estimated ? "text-zinc-500" : "text-zinc-200",
+                          ].join(" ")}
+                          title={r.estimated ? "估算值，正在精确统计" : undefined}
+                        >
+                          {r.estimated ? "≈ " : ""}
+                          {formatBytes(r.totalBytes)}
+                          {r.errorBytes ? (
+                            <span className="ml-1 text-xs text-zinc-500">
+                              ±{formatBytes(r.errorBytes)}
+                            </span>
+                          ) : null}
+                          {r.incomplete ? (
+                            <span
+                              className="ml-1 text-xs text-amber-400"
+                              title="部分路径无法读取，实际占用可能更大；详见深度扫描报告"
+                            >
+                              不完整
+                            </span>
+                          ) : null}
+                        </div>
+                        {r.totalAllocatedBytes !== r.totalBytes ? (
+                          <div className="text-xs tabular-nums text-zinc-500">
+                            实际占用 {formatBytes(r.totalAllocatedBytes)}
+                          </div>
+                        ) : null}
+                        {r.followedVolumes.map((v) => (
+                          <div key={v.volume} className="text-xs tabular-nums text-sky-400">
+                            另在 {v.volume} 上 {formatBytes(v.bytes)}
+                          </div>
+                        ))}
                       </div>
                       <ChevronDown
                         className={[
@@ -85,9 +123,65 @@ export function AppsList(props: {
                               <div className="flex items-center justify-between gap-3">
                                 <div className="text-sm text-zinc-200">{b.label}</div>
                                 <div className="text-sm tabular-nums text-zinc-100">
+                                  {b.estimated ? "≈ " : ""}
                                   {formatBytes(b.bytes)}
+                                  {b.errorBytes != null ? (
+                                    <span
+                                      className="ml-1 text-xs text-zinc-500"
+                                      title="抽样统计，约 95% 置信度"
+                                    >
+                                      ±{formatBytes(b.errorBytes)}
+                                    </span>
+                                  ) : null}
+                                  {b.allocatedBytes !== b.bytes ? (
+                                    <span className="ml-2 text-xs text-zinc-500">
+                                      实际占用 {formatBytes(b.allocatedBytes)}
+                                    </span>
+                                  ) : null}
                                 </div>
                               </div>
+                              {b.relocated.map((f) => (
+                                <div
+                                  key={f.path}
+                                  className="truncate text-xs text-sky-400"
+                                  title={`${f.path} → ${f.target}`}
+                                >
+                                  已迁移到 {f.volume}：{f.target}
+                                  {f.bytes != null ? `（${formatBytes(f.bytes)}）` : ""}
+                                </div>
+                              ))}
+                              {b.skippedCount > 0 ? (
+                                <div className="text-xs text-amber-400">
+                                  {b.skippedCount} 处路径无法读取，未计入大小
+                                </div>
+                              ) : null}
+                              {b.lastActivity != null ? (
+                                <div className="text-xs text-zinc-500">
+                                  最后活动 {new Date(b.lastActivity * 1000).toLocaleDateString()}
+                                </div>
+                              ) : null}
+                              {b.sharedOwners.length > 0 ? (
+                                <div className="text-xs text-zinc-400">
+                                  共同拥有：{b.sharedOwners.join("、")}
+                                </div>
+                              ) : null}
+                              {b.categories.length > 0 ? (
+                                <div className="flex flex-wrap gap-1.5">
+                                  {b.categories.map((c) => (
+                                    <span
+                                      key={c.kind}
+                                      className={[
+                                        "rounded-md px-1.5 py-0.5 text-[11px] ring-1",
+                                        c.kind === "cache" || c.kind === "temp"
+                                          ? "bg-emerald-500/10 text-emerald-300 ring-emerald-500/20"
+                                          : "bg-zinc-950/40 text-zinc-400 ring-white/10",
+                                      ].join(" ")}
+                                    >
+                                      {c.label} {formatBytes(c.bytes)}
+                                    </span>
+                                  ))}
+                                </div>
+                              ) : null}
                               {b.paths.length > 0 ? (
                                 <div className="flex flex-col gap-1">
                                   {b.paths.map((p) => (
@@ -101,6 +195,13 @@ export function AppsList(props: {
                                   ))}
                                 </div>
                               ) : null}
+                              {r.estimated ? null : (
+                                <BreakdownBrowser
+                                  appId={r.id}
+                                  kind={b.kind}
+                                  formatBytes={formatBytes}
+                                />
+                              )}
                             </div>
                           ))}
                         </div>
@@ -1,27 +1,46 @@
 import { AnimatePresence, motion } from "framer-motion";
 import { ChevronDown } from "lucide-react";
+import { useRef, useState } from "react";
 import type { AuditOverview } from "../../../types/apps";
 
 export function AuditPanel(props: {
   audit: AuditOverview | null;
+  auditError: string | null;
   auditLoading: boolean;
+  canLoad: boolean;
   auditOpen: boolean;
-  auditSizes: Record<string, number>;
+  assignFolder: (kind: string, folder: string, appId: string) => void | Promise<void>;
+  downloadCorrections: () => void | Promise<void>;
+  uploadCorrections: (file: File) => Promise<number>;
   formatBytes: (bytes: number) => string;
   loadAudit: () => void | Promise<void>;
-  measureAuditFolder: (kind: string, folder: string) => void | Promise<void>;
   setAuditOpen: (value: boolean | ((prev: boolean) => boolean)) => void;
 }) {
   const {
     audit,
+    auditError,
     auditLoading,
     auditOpen,
-    auditSizes,
+    canLoad,
+    assignFolder,
+    downloadCorrections,
+    uploadCorrections,
     formatBytes,
     loadAudit,
-    measureAuditFolder,
     setAuditOpen,
   } = props;
+  const importInput = useRef<HTMLInputElement>(null);
+  const [importMessage, setImportMessage] = useState<string | null>(null);
+
+  const onImportFile = async (file: File | undefined) => {
+    if (!file) return;
+    try {
+      const added = await uploadCorrections(file);
+      setImportMessage(`已导入 ${added} 条修正，重新扫描后生效`);
+    } catch (e) {
+      setImportMessage(`导入失败：${String(e)}`);
+    }
+  };
 
   return (
     <div className="flex flex-col gap-3 rounded-2xl bg-zinc-900/30 p-4 ring-1 ring-white/10">
@@ -30,12 +49,14 @@ export function AuditPanel(props: {
         <button
           type="button"
           onClick={loadAudit}
-          disabled={auditLoading}
+          disabled={auditLoading || !canLoad}
+          title={canLoad ? undefined : "基于最近一次扫描的结果，请先完成扫描"}
           className="inline-flex h-9 items-center justify-center rounded-xl bg-zinc-950/40 px-3 text-xs font-medium text-zinc-100 ring-1 ring-white/10 transition enabled:hover:bg-white/5 disabled:opacity-60"
         >
-          {auditLoading ? "正在深度扫描…" : "开始深度扫描"}
+          {auditLoading ? "正在分析…" : "开始深度扫描"}
         </button>
       </div>
+      {auditError ? <div className="text-xs text-rose-400">{auditError}</div> : null}
 
       {audit ? (
         <div className="flex flex-col gap-3">
@@ -88,12 +109,186 @@ export function AuditPanel(props: {
                           <div className="truncate">{r.kind}</div>
                           <div className="tabular-nums">
                             已关联 {r.assignedFolders} / 待确定 {r.unassignedFolders}
+                            {r.skippedPaths > 0 ? ` / 无法读取 ${r.skippedPaths}` : ""}
                           </div>
                         </div>
                       ))}
                     </div>
                   </div>
 
+                  <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
+                    <div className="flex items-center justify-between gap-3">
+                      <div className="text-xs font-medium text-zinc-200">归属修正</div>
+                      <div className="flex items-center gap-2">
+                        <button
+                          type="button"
+                          onClick={downloadCorrections}
+                          className="inline-flex h-8 items-center justify-center rounded-lg bg-zinc-950/40 px-2 text-[11px] text-zinc-200 ring-1 ring-white/10 transition hover:bg-white/5"
+                        >
+                          导出修正
+                        </button>
+                        <button
+                          type="button"
+                          onClick={() => importInput.current?.click()}
+                          className="inline-flex h-8 items-center justify-center rounded-lg bg-zinc-950/40 px-2 text-[11px] text-zinc-200 ring-1 ring-white/10 transition hover:bg-white/5"
+                        >
+                          导入修正
+                        </button>
+                        <input
+                          ref={importInput}
+                          type="file"
+                          accept="application/json,.json"
+                          className="hidden"
+                          onChange={(e) => {
+                            void onImportFile(e.target.files?.[0]);
+                            e.target.value = "";
+                          }}
+                        />
+                      </div>
+                    </div>
+                    {importMessage ? (
+                      <div className="mt-2 text-xs text-zinc-400">{importMessage}</div>
+                    ) : null}
+                  </div>
+
+                  {audit.orphanedApps.length > 0 ? (
+                    <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
+                      <div className="text-xs font-medium text-zinc-200">
+                        已卸载软件的残留数据
+                      </div>
+                      <div className="mt-2 flex flex-col gap-2">
+                        {audit.orphanedApps.slice(0, 20).map((a) => (
+                          <div key={a.id} className="flex flex-col gap-1">
+                            <div className="flex items-center justify-between gap-3 text-xs">
+                              <div className="min-w-0 truncate text-zinc-300">
+                                来自 {a.name} 的残留数据，卸载于{" "}
+                                {new Date(a.uninstalledAt * 1000).toLocaleDateString()}
+                              </div>
+                              <span className="shrink-0 tabular-nums text-zinc-200">
+                                {formatBytes(a.totalBytes)}
+                              </span>
+                            </div>
+                            {a.folders.map((f) => (
+                              <div
+                                key={f.path}
+                                className="flex items-center justify-between gap-3 text-[11px]"
+                              >
+                                <div
+                                  className="min-w-0 truncate font-mono text-zinc-500"
+                                  title={f.path}
+                                >
+                                  {f.path}
+                                </div>
+                                <span className="shrink-0 tabular-nums text-zinc-500">
+                                  {formatBytes(f.bytes)}
+                                </span>
+                              </div>
+                            ))}
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  ) : null}
+
+                  {audit.brokenUninstallEntries.length > 0 ? (
+                    <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
+                      <div className="text-xs font-medium text-zinc-200">
+                        失效的卸载条目（共 {audit.brokenUninstallEntries.length} 个）
+                      </div>
+                      <div className="mt-2 flex flex-col gap-2">
+                        {audit.brokenUninstallEntries.slice(0, 30).map((e) => (
+                          <div key={e.id} className="flex flex-col gap-0.5 text-xs">
+                            <div className="truncate text-zinc-300">{e.name}</div>
+                            {e.registryKeys.map((k) => (
+                              <div
+                                key={k}
+                                className="truncate font-mono text-[11px] text-zinc-500"
+                                title={k}
+                              >
+                                {k}
+                              </div>
+                            ))}
+                            {e.issues.map((i) => (
+                              <div key={i.reason} className="truncate text-zinc-400" title={i.path}>
+                                {i.label}
+                                {i.reason === "sizeMismatch" &&
+                                i.estimatedBytes != null &&
+                                i.measuredBytes != null
+                                  ? `：登记 ${formatBytes(i.estimatedBytes)}，实际 ${formatBytes(i.measuredBytes)}`
+                                  : `：${i.path}`}
+                              </div>
+                            ))}
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  ) : null}
+
+                  {audit.staleApps.length > 0 ? (
+                    <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
+                      <div className="text-xs font-medium text-zinc-200">
+                        长期未使用的大型软件（建议优先卸载）
+                      </div>
+                      <div className="mt-2 flex flex-col gap-1.5">
+                        {audit.staleApps.slice(0, 20).map((a) => (
+                          <div key={a.id} className="flex items-center justify-between gap-3 text-xs">
+                            <div className="min-w-0 truncate text-zinc-300">{a.name}</div>
+                            <div className="flex shrink-0 items-center gap-3 tabular-nums">
+                              <span className="text-zinc-500">
+                                最后活动 {new Date(a.lastActivity * 1000).toLocaleDateString()}
+                              </span>
+                              <span className="text-zinc-200">{formatBytes(a.totalBytes)}</span>
+                            </div>
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  ) : null}
+
+                  {audit.skippedPaths.length > 0 ? (
+                    <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
+                      <div className="text-xs font-medium text-zinc-200">
+                        无法读取的路径（共 {audit.skippedPathCount} 处，相关大小可能偏小）
+                      </div>
+                      <div className="mt-2 flex flex-col gap-1.5">
+                        {audit.skippedPaths.slice(0, 40).map((p) => (
+                          <div key={p.path} className="flex items-center justify-between gap-3 text-xs">
+                            <div
+                              className="min-w-0 truncate font-mono text-[11px] text-zinc-500"
+                              title={p.path}
+                            >
+                              {p.path}
+                            </div>
+                            <div className="shrink-0 text-zinc-400">{p.label}</div>
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  ) : null}
+
+                  {audit.relocatedFolders.length > 0 ? (
+                    <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
+                      <div className="text-xs font-medium text-zinc-200">
+                        通过链接迁移的文件夹（共 {audit.relocatedFolders.length} 个）
+                      </div>
+                      <div className="mt-2 flex flex-col gap-1.5">
+                        {audit.relocatedFolders.slice(0, 40).map((f) => (
+                          <div key={f.path} className="flex flex-col gap-0.5 text-xs">
+                            <div
+                              className="truncate font-mono text-[11px] text-zinc-500"
+                              title={f.path}
+                            >
+                              {f.path}
+                            </div>
+                            <div className="truncate text-zinc-400" title={f.target}>
+                              → {f.volume} {f.target}
+                            </div>
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  ) : null}
+
                   {audit.duplicateInstallLocations.length > 0 ? (
                     <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
                       <div className="text-xs font-medium text-zinc-200">
@@ -122,43 +317,45 @@ export function AuditPanel(props: {
                     <div className="rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10">
                       <div className="text-xs font-medium text-zinc-200">未关联到软件的文件夹 (前 40 个)</div>
                       <div className="mt-2 flex flex-col gap-2">
-                        {audit.unassignedFolders.slice(0, 40).map((u) => {
-                          const key = `${u.kind}:${u.folder}`;
-                          const bytes = auditSizes[key];
-                          return (
-                            <div
-                              key={u.path}
-                              className="flex items-start justify-between gap-3 rounded-lg bg-black/20 px-2 py-2"
-                            >
-                              <div className="min-w-0">
-                                <div className="text-xs text-zinc-300">
-                                  {u.kind} / {u.folder}
-                                </div>
-                                <div
-                                  className="truncate font-mono text-[11px] text-zinc-500"
-                                  title={u.path}
-                                >
-                                  {u.path}
-                                </div>
+                        {audit.unassignedFolders.slice(0, 40).map((u) => (
+                          <div
+                            key={u.path}
+                            className="flex items-start justify-between gap-3 rounded-lg bg-black/20 px-2 py-2"
+                          >
+                            <div className="min-w-0">
+                              <div className="text-xs text-zinc-300">
+                                {u.kind} / {u.folder}
+                              </div>
+                              <div
+                                className="truncate font-mono text-[11px] text-zinc-500"
+                                title={u.path}
+                              >
+                                {u.path}
                               </div>
-                              <div className="flex shrink-0 items-center gap-2">
-                                {bytes != null ? (
-                                  <div className="text-xs tabular-nums text-zinc-200">
-                                    {formatBytes(bytes)}
-                                  </div>
-                                ) : (
-                                  <button
-                                    type="button"
-                                    onClick={() => measureAuditFolder(u.kind, u.folder)}
-                                    className="inline-flex h-8 items-center justify-center rounded-lg bg-zinc-950/40 px-2 text-[11px] text-zinc-200 ring-1 ring-white/10 transition hover:bg-white/5"
-                                  >
-                                    分析大小
-                                  </button>
-                                )}
+                            </div>
+                            <div className="flex shrink-0 items-center gap-2">
+                              <select
+                                value=""
+                                onChange={(e) => {
+                                  if (e.target.value) {
+                                    void assignFolder(u.kind, u.folder, e.target.value);
+                                  }
+                                }}
+                                className="h-8 max-w-[140px] rounded-lg bg-zinc-950/40 px-2 text-[11px] text-zinc-200 ring-1 ring-white/10"
+                              >
+                                <option value="">归属到…</option>
+                                {audit.apps.map((a) => (
+                                  <option key={a.id} value={a.id}>
+                                    {a.name}
+                                  </option>
+                                ))}
+                              </select>
+                              <div className="text-xs tabular-nums text-zinc-200">
+                                {formatBytes(u.bytes)}
                               </div>
                             </div>
-                          );
-                        })}
+                          </div>
+                        ))}
                       </div>
                     </div>
                   ) : null}
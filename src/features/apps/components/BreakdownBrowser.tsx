@@ -0,0 +1,105 @@
+import { ArrowUp, File, Folder } from "lucide-react";
+import { useCallback, useState } from "react";
+import { drillDownApp } from "../../../lib/tauri/apps";
+import type { DrillDownLevel } from "../../../types/apps";
+
+interface BreakdownBrowserProps {
+  appId: string;
+  kind: string;
+  formatBytes: (bytes: number) => string;
+}
+
+/** 像 ncdu 一样逐层查看明细条目下最大的子目录与文件。 */
+export function BreakdownBrowser({ appId, kind, formatBytes }: BreakdownBrowserProps) {
+  const [level, setLevel] = useState<DrillDownLevel | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const load = useCallback(
+    async (path: string | null) => {
+      setLoading(true);
+      setError(null);
+      try {
+        setLevel(await drillDownApp(appId, kind, path));
+      } catch (e) {
+        setError(String(e));
+      } finally {
+        setLoading(false);
+      }
+    },
+    [appId, kind],
+  );
+
+  if (!level) {
+    return (
+      <div className="flex items-center gap-2">
+        <button
+          type="button"
+          onClick={() => load(null)}
+          disabled={loading}
+          className="inline-flex h-7 items-center justify-center rounded-lg bg-zinc-950/40 px-2 text-[11px] text-zinc-200 ring-1 ring-white/10 transition enabled:hover:bg-white/5 disabled:opacity-60"
+        >
+          {loading ? "读取中…" : "查看最大的文件"}
+        </button>
+        {error ? <span className="text-[11px] text-rose-400">{error}</span> : null}
+      </div>
+    );
+  }
+
+  const max = level.entries[0]?.bytes || 1;
+
+  return (
+    <div className="flex flex-col gap-1.5 rounded-lg bg-black/20 p-2">
+      <div className="flex items-center gap-2">
+        <button
+          type="button"
+          onClick={() => (level.path === null ? setLevel(null) : load(level.parent))}
+          disabled={loading}
+          className="inline-flex h-6 w-6 shrink-0 items-center justify-center rounded-md text-zinc-400 ring-1 ring-white/10 transition enabled:hover:bg-white/5"
+          title={level.path === null ? "收起" : "上一层"}
+        >
+          <ArrowUp className="h-3.5 w-3.5" />
+        </button>
+        <div
+          className="truncate font-mono text-[11px] text-zinc-500"
+          title={level.path ?? undefined}
+        >
+          {level.path ?? "全部目录"}
+        </div>
+      </div>
+      {error ? <div className="text-[11px] text-rose-400">{error}</div> : null}
+      {level.entries.map((e) => (
+        <button
+          key={e.path}
+          type="button"
+          onClick={() => (e.isDir ? load(e.path) : undefined)}
+          disabled={loading || !e.isDir}
+          className="flex flex-col gap-1 rounded-md px-1.5 py-1 text-left transition enabled:hover:bg-white/5"
+        >
+          <div className="flex items-center justify-between gap-3 text-[11px]">
+            <div className="flex min-w-0 items-center gap-1.5 text-zinc-300" title={e.path}>
+              {e.isDir ? (
+                <Folder className="h-3.5 w-3.5 shrink-0 text-zinc-500" />
+              ) : (
+                <File className="h-3.5 w-3.5 shrink-0 text-zinc-600" />
+              )}
+              <span className="truncate">{e.name}</span>
+            </div>
+            <div className="shrink-0 font-mono text-zinc-300">{formatBytes(e.bytes)}</div>
+          </div>
+          <div className="h-1 w-full overflow-hidden rounded-full bg-zinc-950/40">
+            <div
+              className="h-full rounded-full bg-indigo-500/70"
+              style={{ width: `${Math.max(1, (e.bytes / max) * 100)}%` }}
+            />
+          </div>
+        </button>
+      ))}
+      {level.remainingCount > 0 ? (
+        <div className="px-1.5 text-[11px] text-zinc-500">
+          其余 {level.remainingCount} 项，共 {formatBytes(level.remainingBytes)}
+        </div>
+      ) : null}
+    </div>
+  );
+}
@@ -0,0 +1,93 @@
+import { Copy } from "lucide-react";
+import type { DuplicateReport, ScanProgress } from "../../../types/apps";
+
+interface DuplicatesPanelProps {
+  canFind: boolean;
+  error: string | null;
+  formatBytes: (bytes: number) => string;
+  isFinding: boolean;
+  progress: ScanProgress | null;
+  report: DuplicateReport | null;
+  onCancel: () => void | Promise<void>;
+  onFind: () => void | Promise<void>;
+}
+
+const PHASE_LABELS: Record<string, string> = {
+  collect: "收集文件",
+  partial: "比较开头",
+  full: "比较内容",
+};
+
+/** 跨软件的重复文件：同一运行时（Electron、CEF、JRE 等）被多个软件各自打包的情况。 */
+export function DuplicatesPanel(props: DuplicatesPanelProps) {
+  const { canFind, error, formatBytes, isFinding, progress, report, onCancel, onFind } = props;
+
+  return (
+    <div className="flex flex-col gap-3 rounded-2xl bg-zinc-900/50 p-4 ring-1 ring-white/10">
+      <div className="flex items-center justify-between gap-3">
+        <div className="flex items-center gap-2 text-sm text-zinc-300">
+          <Copy className="h-4 w-4" />
+          <span>重复文件</span>
+          {report ? (
+            <span className="text-xs text-zinc-500">
+              {report.setCount} 组，可节省 {formatBytes(report.wastedBytes)}
+            </span>
+          ) : null}
+        </div>
+        <button
+          type="button"
+          onClick={isFinding ? onCancel : onFind}
+          disabled={!isFinding && !canFind}
+          className="inline-flex h-8 items-center justify-center rounded-lg bg-zinc-950/40 px-3 text-xs text-zinc-200 ring-1 ring-white/10 transition enabled:hover:bg-white/5 disabled:opacity-60"
+          title={canFind ? undefined : "请先完成一次扫描"}
+        >
+          {isFinding ? "停止（可继续）" : report ? "重新查找" : "查找重复文件"}
+        </button>
+      </div>
+
+      {isFinding ? (
+        <div className="flex items-center justify-between gap-3 text-xs text-zinc-500">
+          <div>{progress ? PHASE_LABELS[progress.phase] ?? progress.message : "准备中…"}</div>
+          {progress && progress.total > 0 ? (
+            <div className="tabular-nums">
+              {progress.current}/{progress.total}
+            </div>
+          ) : null}
+        </div>
+      ) : null}
+      {error ? <div className="text-xs text-rose-400">{error}</div> : null}
+
+      {report && !isFinding ? (
+        <div className="flex flex-col gap-2">
+          <div className="text-[11px] text-zinc-500">
+            比较了 {report.filesScanned} 个文件，复用上次的哈希 {report.reusedHashes} 个
+          </div>
+          {report.sets.slice(0, 40).map((s) => (
+            <div
+              key={s.files[0]?.path}
+              className="flex flex-col gap-1 rounded-xl bg-zinc-950/40 p-3 ring-1 ring-white/10"
+            >
+              <div className="flex items-center justify-between gap-3 text-xs">
+                <div className="min-w-0 truncate text-zinc-300">
+                  {s.files.length} 份 × {formatBytes(s.fileBytes)} · {s.apps.join("、")}
+                </div>
+                <div className="shrink-0 tabular-nums text-zinc-100">
+                  浪费 {formatBytes(s.wastedBytes)}
+                </div>
+              </div>
+              {s.files.map((f) => (
+                <div
+                  key={f.path}
+                  className="truncate font-mono text-[11px] text-zinc-500"
+                  title={f.apps.join("、")}
+                >
+                  {f.path}
+                </div>
+              ))}
+            </div>
+          ))}
+        </div>
+      ) : null}
+    </div>
+  );
+}
@@ -0,0 +1,71 @@
+import { Database } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import { clearSizeIndex, getSizeIndexInfo } from "../../../lib/tauri/apps";
+import type { SizeIndexInfo } from "../../../types/apps";
+
+interface SizeIndexPanelProps {
+  formatBytes: (bytes: number) => string;
+  isScanning: boolean;
+}
+
+export function SizeIndexPanel({ formatBytes, isScanning }: SizeIndexPanelProps) {
+  const [info, setInfo] = useState<SizeIndexInfo | null>(null);
+  const [clearing, setClearing] = useState(false);
+
+  const loadInfo = useCallback(async () => {
+    try {
+      setInfo(await getSizeIndexInfo());
+    } catch (err) {
+      console.error("Failed to load size index info", err);
+    }
+  }, []);
+
+  // 扫描结束后索引会被重写，重新读取一次概况。
+  useEffect(() => {
+    if (!isScanning) loadInfo();
+  }, [isScanning, loadInfo]);
+
+  const onClear = async () => {
+    setClearing(true);
+    try {
+      await clearSizeIndex();
+      await loadInfo();
+    } finally {
+      setClearing(false);
+    }
+  };
+
+  if (!info) return null;
+
+  return (
+    <div className="flex flex-col gap-3 rounded-2xl bg-zinc-900/40 p-5 ring-1 ring-white/10 backdrop-blur-sm">
+      <div className="flex items-center justify-between gap-3">
+        <div className="flex items-center gap-2 text-sm font-semibold text-zinc-100">
+          <Database className="h-4 w-4 text-zinc-400" />
+          <span>大小索引</span>
+        </div>
+        <button
+          type="button"
+          onClick={onClear}
+          disabled={clearing || isScanning || info.directoryCount === 0}
+          className="inline-flex h-8 items-center justify-center rounded-lg bg-zinc-950/40 px-2 text-[11px] text-zinc-200 ring-1 ring-white/10 transition enabled:hover:bg-white/5 disabled:opacity-60"
+        >
+          清除索引
+        </button>
+      </div>
+      <div className="text-xs leading-relaxed text-zinc-400">
+        {info.directoryCount > 0 ? (
+          <>
+            已索引 {info.directoryCount} 个目录，占用 {formatBytes(info.indexBytes)}
+            {info.updatedAt != null
+              ? `，更新于 ${new Date(info.updatedAt * 1000).toLocaleString()}`
+              : ""}
+            。未变化的目录在下次扫描时直接复用。
+          </>
+        ) : (
+          "尚未建立索引，首次扫描完成后自动生成。"
+        )}
+      </div>
+    </div>
+  );
+}
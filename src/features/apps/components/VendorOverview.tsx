@@ -0,0 +1,42 @@
+import { Building2 } from "lucide-react";
+import type { VendorSummary } from "../../../types/apps";
+
+interface VendorOverviewProps {
+  vendors: VendorSummary[];
+  formatBytes: (bytes: number) => string;
+}
+
+export function VendorOverview({ vendors, formatBytes }: VendorOverviewProps) {
+  if (vendors.length === 0) return null;
+
+  const top = vendors.slice(0, 10);
+  const max = top[0]?.totalBytes || 1;
+
+  return (
+    <div className="flex flex-col gap-4 rounded-2xl bg-zinc-900/40 p-5 ring-1 ring-white/10 backdrop-blur-sm">
+      <div className="flex items-center gap-2 text-sm font-semibold text-zinc-100">
+        <Building2 className="h-4 w-4 text-zinc-400" />
+        <span>厂商占用 Top 10</span>
+      </div>
+      <div className="flex flex-col gap-3">
+        {top.map((v) => (
+          <div key={v.id} className="flex flex-col gap-1">
+            <div className="flex items-center justify-between gap-3 text-[11px]">
+              <div className="min-w-0 truncate text-zinc-300" title={v.name}>
+                {v.name}
+                <span className="ml-1.5 text-zinc-500">{v.appCount} 个软件</span>
+              </div>
+              <div className="shrink-0 font-mono text-zinc-300">{formatBytes(v.totalBytes)}</div>
+            </div>
+            <div className="h-1.5 w-full overflow-hidden rounded-full bg-zinc-950/40">
+              <div
+                className="h-full rounded-full bg-indigo-500/80"
+                style={{ width: `${Math.max(2, (v.totalBytes / max) * 100)}%` }}
+              />
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
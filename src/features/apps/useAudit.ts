@@ -1,48 +1,79 @@
 import { useCallback, useState } from "react";
-import type { AuditOverview } from "../../types/apps";
-import { getAuditOverview, measureAuditFolderSize } from "../../lib/tauri/apps";
+import type { AppRecord, AuditOverview } from "../../types/apps";
+import {
+  assignAuditFolder,
+  exportCorrections,
+  getAuditOverview,
+  importCorrections,
+} from "../../lib/tauri/apps";
 
-export function useAudit() {
+/** `onRecordUpdated` 接收手动归属后该软件更新的记录。 */
+export function useAudit(onRecordUpdated: (record: AppRecord) => void) {
   const [audit, setAudit] = useState<AuditOverview | null>(null);
   const [auditLoading, setAuditLoading] = useState(false);
-  const [auditSizes, setAuditSizes] = useState<Record<string, number>>({});
   const [auditOpen, setAuditOpen] = useState(false);
+  const [auditError, setAuditError] = useState<string | null>(null);
 
   const loadAudit = useCallback(async () => {
     if (auditLoading) return;
     setAuditLoading(true);
+    setAuditError(null);
     try {
+      // 审计基于最近一次完成的扫描，尚未扫描时后端返回错误。
       const result = await getAuditOverview();
       setAudit(result);
       setAuditOpen(true);
+    } catch (e) {
+      setAuditError(String(e));
     } finally {
       setAuditLoading(false);
     }
   }, [auditLoading]);
 
-  const measureAuditFolder = useCallback(
-    async (kind: string, folder: string) => {
-    const key = `${kind}:${folder}`;
-    if (auditSizes[key] != null) return;
-    const bytes = await measureAuditFolderSize(kind, folder);
-    setAuditSizes((prev) => ({ ...prev, [key]: bytes }));
-    },
-    [auditSizes],
-  );
+  const assignFolder = useCallback(async (kind: string, folder: string, appId: string) => {
+    const record = await assignAuditFolder(kind, folder, appId);
+    onRecordUpdated(record);
+    setAudit((prev) =>
+      prev
+        ? {
+            ...prev,
+            unassignedFolders: prev.unassignedFolders.filter(
+              (f) => !(f.kind === kind && f.folder === folder),
+            ),
+          }
+        : prev,
+    );
+  }, [onRecordUpdated]);
+
+  const downloadCorrections = useCallback(async () => {
+    const json = await exportCorrections();
+    const url = URL.createObjectURL(new Blob([json], { type: "application/json" }));
+    const a = document.createElement("a");
+    a.href = url;
+    a.download = "appmanager-corrections.json";
+    a.click();
+    URL.revokeObjectURL(url);
+  }, []);
+
+  const uploadCorrections = useCallback(async (file: File) => {
+    return importCorrections(await file.text());
+  }, []);
 
   const resetAudit = useCallback(() => {
     setAudit(null);
-    setAuditSizes({});
     setAuditOpen(false);
+    setAuditError(null);
   }, []);
 
   return {
     audit,
+    auditError,
     auditLoading,
     auditOpen,
-    auditSizes,
+    assignFolder,
+    downloadCorrections,
+    uploadCorrections,
     loadAudit,
-    measureAuditFolder,
     resetAudit,
     setAuditOpen,
   };
@@ -0,0 +1,80 @@
+import { useCallback, useEffect, useRef, useState } from "react";
+import type { DuplicateReport, ScanProgress } from "../../types/apps";
+import {
+  cancelFindDuplicates,
+  listenDuplicateEvents,
+  startFindDuplicates,
+} from "../../lib/tauri/apps";
+
+export function useDuplicates() {
+  const [report, setReport] = useState<DuplicateReport | null>(null);
+  const [progress, setProgress] = useState<ScanProgress | null>(null);
+  const [isFinding, setIsFinding] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  // 与扫描相同：事件可能早于 startFindDuplicates 返回，只丢弃更旧任务的事件。
+  const jobIdRef = useRef(0);
+
+  const isCurrentJob = useCallback((jobId: number) => {
+    if (jobId < jobIdRef.current) return false;
+    jobIdRef.current = jobId;
+    return true;
+  }, []);
+
+  const find = useCallback(async () => {
+    if (isFinding) return;
+    setIsFinding(true);
+    setProgress(null);
+    setError(null);
+    try {
+      const id = await startFindDuplicates();
+      jobIdRef.current = Math.max(jobIdRef.current, id);
+    } catch (e) {
+      setError(String(e));
+      setIsFinding(false);
+    }
+  }, [isFinding]);
+
+  const cancel = useCallback(async () => {
+    if (!isFinding || jobIdRef.current === 0) return;
+    try {
+      await cancelFindDuplicates(jobIdRef.current);
+    } finally {
+      setIsFinding(false);
+    }
+  }, [isFinding]);
+
+  const resetDuplicates = useCallback(() => {
+    setReport(null);
+    setProgress(null);
+  }, []);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    (async () => {
+      try {
+        unlisten = await listenDuplicateEvents({
+          onProgress: (jobId, p) => {
+            if (isCurrentJob(jobId)) setProgress(p);
+          },
+          onDone: (jobId, r) => {
+            if (!isCurrentJob(jobId)) return;
+            setReport(r);
+            setIsFinding(false);
+          },
+          onCancelled: (jobId) => {
+            if (isCurrentJob(jobId)) setIsFinding(false);
+          },
+        });
+      } catch {
+        unlisten = null;
+      }
+    })();
+
+    return () => {
+      unlisten?.();
+    };
+  }, [isCurrentJob]);
+
+  return { cancel, error, find, isFinding, progress, report, resetDuplicates };
+}
@@ -1,12 +1,39 @@
-import { useCallback, useEffect, useMemo, useState } from "react";
-import type { AppRecord, ScanProgress } from "../../types/apps";
-import { listenScanEvents, startScanApps } from "../../lib/tauri/apps";
+import { useCallback, useEffect, useMemo, useRef, useState } from "react";
+import type {
+  AppRecord,
+  BackgroundOptions,
+  LinkPolicy,
+  ScanProgress,
+  VendorSummary,
+} from "../../types/apps";
+import {
+  cancelScan,
+  listenScanEvents,
+  pauseScan,
+  resumeScan,
+  startScanApps,
+} from "../../lib/tauri/apps";
 
 export function useScanApps() {
   const [rows, setRows] = useState<AppRecord[]>([]);
   const [isScanning, setIsScanning] = useState(false);
   const [expanded, setExpanded] = useState<Record<string, boolean>>({});
   const [progress, setProgress] = useState<ScanProgress | null>(null);
+  // 以抽样统计代替精确统计的条目类型，适合数 TB 的游戏库或虚拟机目录。
+  const [approximateKinds, setApproximateKinds] = useState<string[]>([]);
+  // 迁移到其他盘的数据默认不计入，选择跟随时计入并注明所在的卷。
+  const [linkPolicy, setLinkPolicy] = useState<LinkPolicy>("skip");
+  // 后台模式：限速、低优先级，扫描期间不拖慢其他程序；为 null 时全速扫描。
+  const [background, setBackground] = useState<BackgroundOptions | null>(null);
+  const [isPaused, setIsPaused] = useState(false);
+  // 扫描 id 单调递增；事件可能早于 startScanApps 返回，因此只丢弃比当前更旧的扫描。
+  const scanIdRef = useRef(0);
+
+  const isCurrentScan = useCallback((scanId: number) => {
+    if (scanId < scanIdRef.current) return false;
+    scanIdRef.current = scanId;
+    return true;
+  }, []);
 
   const stats = useMemo(() => {
     const categories: Record<string, number> = {};
@@ -42,41 +69,114 @@ export function useScanApps() {
         return entry;
       });
 
-    return { categoryData, topAppsData };
+    const vendors: Record<string, VendorSummary> = {};
+    rows.forEach((app) => {
+      if (!app.vendorId) return;
+      if (!vendors[app.vendorId]) {
+        vendors[app.vendorId] = {
+          id: app.vendorId,
+          name: app.vendorName ?? app.vendorId,
+          appCount: 0,
+          totalBytes: 0,
+        };
+      }
+      const v = vendors[app.vendorId];
+      v.appCount += 1;
+      v.totalBytes += app.totalBytes;
+    });
+
+    const vendorData = Object.values(vendors)
+      .filter((v) => v.totalBytes > 0)
+      .sort((a, b) => b.totalBytes - a.totalBytes);
+
+    return { categoryData, topAppsData, vendorData };
   }, [rows]);
 
   const scan = useCallback(async () => {
     if (isScanning) return;
     setIsScanning(true);
+    setIsPaused(false);
     setProgress(null);
     setRows([]);
     setExpanded({});
 
     try {
-      await startScanApps();
+      // 列表按大小排序，先统计大的软件可让列表顶部尽早稳定。
+      const id = await startScanApps({
+        largestFirst: true,
+        approximate: approximateKinds,
+        linkPolicy,
+        background: background ?? undefined,
+      });
+      scanIdRef.current = Math.max(scanIdRef.current, id);
     } catch {
       setIsScanning(false);
     }
+  }, [approximateKinds, background, isScanning, linkPolicy]);
+
+  const cancel = useCallback(async () => {
+    if (!isScanning || scanIdRef.current === 0) return;
+    try {
+      await cancelScan(scanIdRef.current);
+    } finally {
+      setIsScanning(false);
+      setIsPaused(false);
+    }
   }, [isScanning]);
 
+  const togglePause = useCallback(async () => {
+    if (!isScanning || scanIdRef.current === 0) return;
+    if (isPaused) {
+      await resumeScan(scanIdRef.current);
+      setIsPaused(false);
+    } else {
+      await pauseScan(scanIdRef.current);
+      setIsPaused(true);
+    }
+  }, [isPaused, isScanning]);
+
   const toggleExpanded = useCallback((id: string) => {
     setExpanded((prev) => ({ ...prev, [id]: !prev[id] }));
   }, []);
 
+  // 文件监视或手动归属后，原位替换该软件的记录。
+  const updateRow = useCallback((rec: AppRecord) => {
+    setRows((prev) => prev.map((r) => (r.id === rec.id ? rec : r)));
+  }, []);
+
   useEffect(() => {
     let unlisten: (() => void) | null = null;
 
     (async () => {
       try {
         unlisten = await listenScanEvents({
-          onProgress: (p) => setProgress(p),
-          onRecord: (rec) => {
+          onProgress: (scanId, p) => {
+            if (isCurrentScan(scanId)) setProgress(p);
+          },
+          onRecord: (scanId, rec) => {
+            if (!isCurrentScan(scanId)) return;
+            // 同一软件先收到估算记录，精确记录到达后原位替换。
             setRows((prev) => {
-              if (prev.some((r) => r.id === rec.id)) return prev;
+              if (prev.some((r) => r.id === rec.id)) {
+                return prev.map((r) => (r.id === rec.id ? rec : r));
+              }
               return [...prev, rec];
             });
           },
-          onDone: () => setIsScanning(false),
+          onUpdate: (scanId, rec) => {
+            if (!isCurrentScan(scanId)) return;
+            updateRow(rec);
+          },
+          onDone: (scanId) => {
+            if (!isCurrentScan(scanId)) return;
+            setIsScanning(false);
+            setIsPaused(false);
+          },
+          onCancelled: (scanId) => {
+            if (!isCurrentScan(scanId)) return;
+            setIsScanning(false);
+            setIsPaused(false);
+          },
         });
       } catch {
         unlisten = null;
@@ -86,19 +186,29 @@ export function useScanApps() {
     return () => {
       unlisten?.();
     };
-  }, []);
+  }, [isCurrentScan, updateRow]);
 
   return {
+    approximateKinds,
+    background,
+    cancel,
     expanded,
+    isPaused,
     isScanning,
+    linkPolicy,
     progress,
     rows,
     scan,
+    setApproximateKinds,
+    setBackground,
+    setLinkPolicy,
     setExpanded,
     setIsScanning,
     setProgress,
     setRows,
     stats,
     toggleExpanded,
+    togglePause,
+    updateRow,
   };
 }
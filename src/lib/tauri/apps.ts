@@ -1,6 +1,18 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { AppRecord, AuditOverview, DiskInfo, ScanProgress } from "../../types/apps";
+import type {
+  AppRecord,
+  AuditOptions,
+  AuditOverview,
+  DiskInfo,
+  DrillDownLevel,
+  DuplicateOptions,
+  DuplicateReport,
+  ScanEvent,
+  ScanOptions,
+  ScanProgress,
+  SizeIndexInfo,
+} from "../../types/apps";
 
 export type Unlisten = () => void;
 
@@ -8,38 +20,135 @@ export async function getDiskInfo(): Promise<DiskInfo[]> {
   return (await invoke("get_disk_info")) as DiskInfo[];
 }
 
-export async function startScanApps(): Promise<void> {
-  await invoke("start_scan_apps");
+/** 开始扫描并返回扫描 id；正在进行的扫描会被取代。 */
+export async function startScanApps(options?: ScanOptions): Promise<number> {
+  return (await invoke("start_scan_apps", { options })) as number;
+}
+
+export async function cancelScan(id: number): Promise<void> {
+  await invoke("cancel_scan", { id });
+}
+
+export async function pauseScan(id: number): Promise<void> {
+  await invoke("pause_scan", { id });
+}
+
+export async function resumeScan(id: number): Promise<void> {
+  await invoke("resume_scan", { id });
 }
 
 export async function listenScanEvents(options: {
-  onProgress: (progress: ScanProgress) => void;
-  onRecord: (record: AppRecord) => void;
-  onDone: () => void;
+  onProgress: (scanId: number, progress: ScanProgress) => void;
+  onRecord: (scanId: number, record: AppRecord) => void;
+  onUpdate: (scanId: number, record: AppRecord) => void;
+  onDone: (scanId: number) => void;
+  onCancelled: (scanId: number) => void;
 }): Promise<Unlisten> {
-  const unlistenProgress = await listen<ScanProgress>("scan_progress", (event) => {
-    options.onProgress(event.payload);
+  const unlistenProgress = await listen<ScanEvent<ScanProgress>>("scan_progress", (event) => {
+    options.onProgress(event.payload.scanId, event.payload.data);
+  });
+
+  const unlistenResult = await listen<ScanEvent<AppRecord>>("scan_result", (event) => {
+    options.onRecord(event.payload.scanId, event.payload.data);
+  });
+
+  const unlistenUpdate = await listen<ScanEvent<AppRecord>>("scan_update", (event) => {
+    options.onUpdate(event.payload.scanId, event.payload.data);
   });
 
-  const unlistenResult = await listen<AppRecord>("scan_result", (event) => {
-    options.onRecord(event.payload);
+  const unlistenDone = await listen<ScanEvent<null>>("scan_done", (event) => {
+    options.onDone(event.payload.scanId);
   });
 
-  const unlistenDone = await listen("scan_done", () => {
-    options.onDone();
+  const unlistenCancelled = await listen<ScanEvent<null>>("scan_cancelled", (event) => {
+    options.onCancelled(event.payload.scanId);
   });
 
   return () => {
     unlistenProgress();
     unlistenResult();
+    unlistenUpdate();
+    unlistenDone();
+    unlistenCancelled();
+  };
+}
+
+/** 浏览最近一次扫描中某个软件的明细条目，`path` 为空时返回顶层目录。 */
+export async function drillDownApp(
+  appId: string,
+  kind: string,
+  path?: string | null,
+  limit?: number,
+): Promise<DrillDownLevel> {
+  return (await invoke("drill_down_app", { appId, kind, path, limit })) as DrillDownLevel;
+}
+
+/** 在最近一次扫描的归因目录中查找重复文件并返回任务 id；进行中的查找会被取代。 */
+export async function startFindDuplicates(options?: DuplicateOptions): Promise<number> {
+  return (await invoke("start_find_duplicates", { options })) as number;
+}
+
+/** 取消查找；已算出的哈希会保留，下次查找从中断处继续。 */
+export async function cancelFindDuplicates(id: number): Promise<void> {
+  await invoke("cancel_find_duplicates", { id });
+}
+
+export async function listenDuplicateEvents(options: {
+  onProgress: (jobId: number, progress: ScanProgress) => void;
+  onDone: (jobId: number, report: DuplicateReport) => void;
+  onCancelled: (jobId: number) => void;
+}): Promise<Unlisten> {
+  const unlistenProgress = await listen<ScanEvent<ScanProgress>>(
+    "duplicate_progress",
+    (event) => {
+      options.onProgress(event.payload.scanId, event.payload.data);
+    },
+  );
+
+  const unlistenDone = await listen<ScanEvent<DuplicateReport>>("duplicate_done", (event) => {
+    options.onDone(event.payload.scanId, event.payload.data);
+  });
+
+  const unlistenCancelled = await listen<ScanEvent<null>>("duplicate_cancelled", (event) => {
+    options.onCancelled(event.payload.scanId);
+  });
+
+  return () => {
+    unlistenProgress();
     unlistenDone();
+    unlistenCancelled();
   };
 }
 
-export async function getAuditOverview(): Promise<AuditOverview> {
-  return (await invoke("get_audit_overview")) as AuditOverview;
+export async function getAuditOverview(options?: AuditOptions): Promise<AuditOverview> {
+  return (await invoke("get_audit_overview", { options })) as AuditOverview;
 }
 
 export async function measureAuditFolderSize(kind: string, folder: string): Promise<number> {
   return (await invoke("measure_audit_folder_size", { kind, folder })) as number;
 }
+
+/** 手动归属审计报告中的文件夹，返回该软件更新后的记录。 */
+export async function assignAuditFolder(
+  kind: string,
+  folder: string,
+  appId: string,
+): Promise<AppRecord> {
+  return (await invoke("assign_audit_folder", { kind, folder, appId })) as AppRecord;
+}
+
+export async function exportCorrections(): Promise<string> {
+  return (await invoke("export_corrections")) as string;
+}
+
+export async function importCorrections(json: string): Promise<number> {
+  return (await invoke("import_corrections", { json })) as number;
+}
+
+export async function getSizeIndexInfo(): Promise<SizeIndexInfo> {
+  return (await invoke("get_size_index_info")) as SizeIndexInfo;
+}
+
+export async function clearSizeIndex(): Promise<void> {
+  await invoke("clear_size_index");
+}
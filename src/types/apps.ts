@@ -1,16 +1,111 @@
+/** 条目内按文件用途的细分（缓存、日志、数据库等）。 */
+export type CategoryBytes = {
+  kind: string;
+  label: string;
+  bytes: number;
+};
+
 export type AppBreakdownEntry = {
   kind: string;
   label: string;
   bytes: number;
+  allocatedBytes: number;
   paths: string[];
+  sharedOwners: string[];
+  categories: CategoryBytes[];
+  /** 条目内文件最近一次修改或访问的时间（Unix 秒）。 */
+  lastActivity?: number;
+  /** 抽样统计时约 95% 置信度的误差范围（± 字节）。 */
+  errorBytes?: number;
+  /** 因权限不足、被删除或路径过长而未能计入的文件或目录数。 */
+  skippedCount: number;
+  /** 条目目录下通过链接指向别处的文件夹。 */
+  relocated: RelocatedFolder[];
+  /** 快速估算的临时值，稍后会被精确统计结果替换。 */
+  estimated: boolean;
+};
+
+/** 通过符号链接、目录联接或卷挂载点指向别处的文件夹。 */
+export type RelocatedFolder = {
+  path: string;
+  target: string;
+  /** 目标所在的卷，如 "D:"。 */
+  volume: string;
+  /** 跟随链接时统计到的目标大小，不计入所在条目。 */
+  bytes?: number;
+};
+
+/** 跟随链接统计到的、位于某个卷上的大小。 */
+export type VolumeBytes = {
+  volume: string;
+  bytes: number;
 };
 
 export type AppRecord = {
   id: string;
   name: string;
   publisher?: string;
+  vendorId?: string;
+  vendorName?: string;
   totalBytes: number;
+  totalAllocatedBytes: number;
   breakdown: AppBreakdownEntry[];
+  /** 各条目中最新的 lastActivity，即最后一次被使用或改动的大致时间。 */
+  lastActivity?: number;
+  /** 各抽样条目误差合并后的范围。 */
+  errorBytes?: number;
+  /** 有未能读取的路径，totalBytes 可能偏小。 */
+  incomplete: boolean;
+  /** 跟随链接统计到的目标大小，按所在卷汇总，不计入 totalBytes。 */
+  followedVolumes: VolumeBytes[];
+  estimated: boolean;
+};
+
+/** 后端扫描事件的统一外层，`scanId` 用于丢弃已被取代的扫描发出的事件。 */
+export type ScanEvent<T> = {
+  scanId: number;
+  data: T;
+};
+
+export type SharedSplit = "even" | "byScore" | { weights: Record<string, number> };
+
+/** 遇到符号链接、目录联接与卷挂载点时：不跟随、只计链接本身、跟随并注明目标卷。 */
+export type LinkPolicy = "skip" | "linkOnly" | "follow";
+
+/** 后台扫描的限速，0 或不填表示不限。 */
+export type BackgroundOptions = {
+  maxFilesPerSec?: number;
+  maxBytesPerSec?: number;
+};
+
+export type ScanOptions = {
+  sharedSplit?: SharedSplit;
+  largestFirst?: boolean;
+  /** 以抽样统计代替精确统计的条目类型，如 "extraData"、"program"。 */
+  approximate?: string[];
+  linkPolicy?: LinkPolicy;
+  /** 设置后单线程、低优先级并按限速扫描。 */
+  background?: BackgroundOptions;
+};
+
+export type VendorSummary = {
+  id: string;
+  name: string;
+  appCount: number;
+  totalBytes: number;
+};
+
+export type WalkProgress = {
+  filesSeen: number;
+  bytesSeen: number;
+  currentPath: string;
+  filesPerSec: number;
+  etaSecs?: number;
+};
+
+export type PhaseTiming = {
+  phase: string;
+  millis: number;
 };
 
 export type ScanProgress = {
@@ -18,12 +113,15 @@ export type ScanProgress = {
   current: number;
   total: number;
   message: string;
+  walk?: WalkProgress;
+  phaseTimings: PhaseTiming[];
 };
 
 export type AuditRootSummary = {
   kind: string;
   assignedFolders: number;
   unassignedFolders: number;
+  skippedPaths: number;
 };
 
 export type AuditDuplicateInstallLocation = {
@@ -35,6 +133,71 @@ export type AuditUnassignedFolder = {
   kind: string;
   folder: string;
   path: string;
+  bytes: number;
+};
+
+export type AuditApp = {
+  id: string;
+  name: string;
+};
+
+export type AuditStaleApp = {
+  id: string;
+  name: string;
+  totalBytes: number;
+  lastActivity: number;
+};
+
+export type AuditOrphanedFolder = {
+  kind: string;
+  path: string;
+  bytes: number;
+};
+
+export type AuditOrphanedApp = {
+  id: string;
+  name: string;
+  publisher?: string;
+  lastSeen: number;
+  /** 首次发现已卸载的扫描时间；实际卸载发生在 lastSeen 与此之间。 */
+  uninstalledAt: number;
+  totalBytes: number;
+  folders: AuditOrphanedFolder[];
+};
+
+export type AuditUninstallIssue = {
+  reason: "installLocationMissing" | "uninstallerMissing" | "iconMissing" | "sizeMismatch";
+  label: string;
+  path: string;
+  estimatedBytes?: number;
+  measuredBytes?: number;
+};
+
+export type AuditBrokenUninstallEntry = {
+  id: string;
+  name: string;
+  registryKeys: string[];
+  issues: AuditUninstallIssue[];
+};
+
+export type AuditSkippedPath = {
+  /** 所在根目录类型；安装目录为 "program"。 */
+  kind: string;
+  path: string;
+  reason: "permissionDenied" | "vanished" | "pathTooLong" | "other";
+  label: string;
+};
+
+export type AuditRelocatedFolder = {
+  kind: string;
+  path: string;
+  target: string;
+  volume: string;
+};
+
+export type AuditOptions = {
+  staleMonths?: number;
+  staleMinBytes?: number;
 };
 
 export type AuditOverview = {
@@ -43,6 +206,18 @@ export type AuditOverview = {
   roots: AuditRootSummary[];
   duplicateInstallLocations: AuditDuplicateInstallLocation[];
   unassignedFolders: AuditUnassignedFolder[];
+  /** 占用大且长期未使用的软件，按占用降序。 */
+  staleApps: AuditStaleApp[];
+  /** 统计大小时未能读取的路径，最多 200 条。 */
+  skippedPaths: AuditSkippedPath[];
+  skippedPathCount: number;
+  /** 通过链接迁移到别处的文件夹及其实际所在的卷。 */
+  relocatedFolders: AuditRelocatedFolder[];
+  /** 已卸载软件留在 AppData/ProgramData 中的数据，按大小降序。 */
+  orphanedApps: AuditOrphanedApp[];
+  /** 指向不存在的路径、或登记大小与实际明显不符的卸载条目。 */
+  brokenUninstallEntries: AuditBrokenUninstallEntry[];
+  apps: AuditApp[];
 };
 
 export type DiskInfo = {
@@ -52,3 +227,55 @@ export type DiskInfo = {
   availableSpace: number;
   isRemovable: boolean;
 };
+
+export type SizeIndexInfo = {
+  path?: string;
+  directoryCount: number;
+  indexBytes: number;
+  updatedAt?: number;
+};
+
+export type DrillDownEntry = {
+  name: string;
+  path: string;
+  isDir: boolean;
+  bytes: number;
+  allocatedBytes: number;
+};
+
+/** 明细条目逐层浏览的一层；`path` 为 null 时是该条目的顶层目录列表。 */
+export type DrillDownLevel = {
+  path: string | null;
+  parent: string | null;
+  entries: DrillDownEntry[];
+  remainingCount: number;
+  remainingBytes: number;
+};
+
+export type DuplicateOptions = {
+  minFileBytes?: number;
+  limit?: number;
+};
+
+export type DuplicateFile = {
+  path: string;
+  apps: string[];
+};
+
+/** 内容完全相同的一组文件。 */
+export type DuplicateSet = {
+  fileBytes: number;
+  /** 只保留一份时可以省下的空间。 */
+  wastedBytes: number;
+  files: DuplicateFile[];
+  apps: string[];
+};
+
+export type DuplicateReport = {
+  sets: DuplicateSet[];
+  setCount: number;
+  wastedBytes: number;
+  filesScanned: number;
+  /** 从上次（包括被取消的）查找中复用的哈希数。 */
+  reusedHashes: number;
+};